@@ -0,0 +1,137 @@
+use std::path::Path;
+use chrono::{DateTime, Utc};
+use color_eyre::{eyre::eyre, Report};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+/// Environment variable with the bearer token sent to the backend
+pub const TOKEN_VAR: &str = "ARAGOG_BACKEND_TOKEN";
+/// Environment variable with the key used to sign every request body
+pub const SIGNING_KEY_VAR: &str = "ARAGOG_BACKEND_SIGNING_KEY";
+/// Environment variable with the path of a secrets file, see `BackendAuth::load`
+pub const SECRETS_FILE_VAR: &str = "ARAGOG_SECRETS_FILE";
+
+/// Unix time, in seconds, the body was signed at
+pub const TIMESTAMP_HEADER: &str = "X-Aragog-Timestamp";
+/// `sha256=<hex HMAC-SHA256 of "<timestamp>.<body>">`
+pub const SIGNATURE_HEADER: &str = "X-Aragog-Signature";
+
+/// Credentials for the backend. Never read from configuration.yaml, it gets
+/// committed.
+#[derive(Clone, Default)]
+pub struct BackendAuth {
+    token: Option<String>,
+    signing_key: Option<Vec<u8>>,
+}
+
+impl std::fmt::Debug for BackendAuth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BackendAuth")
+            .field("token", &self.token.as_ref().map(|_| "***"))
+            .field("signing_key", &self.signing_key.as_ref().map(|_| "***"))
+            .finish()
+    }
+}
+
+impl BackendAuth {
+
+    pub fn new(token: Option<String>, signing_key: Option<Vec<u8>>) -> BackendAuth {
+        BackendAuth { token, signing_key }
+    }
+
+    /* Secrets come from `ARAGOG_BACKEND_TOKEN` and `ARAGOG_BACKEND_SIGNING_KEY`
+     * or, for whichever is not set, from the secrets file: `KEY=value` lines
+     * with the same names, `#` for comments. The file is `secrets_file` or the
+     * one in `ARAGOG_SECRETS_FILE`. Nothing set means no auth at all.
+     */
+    pub fn load(secrets_file: Option<&Path>) -> Result<BackendAuth, Report> {
+        let from_env = std::env::var(SECRETS_FILE_VAR).ok();
+        let file = match secrets_file.or(from_env.as_deref().map(Path::new)) {
+            Some(path) => {
+                let contents = std::fs::read_to_string(path)
+                    .map_err(|e| eyre!("Unable to read secrets file {}: {}", path.display(), e))?;
+                parse_secrets(&contents)
+            }
+            None => vec![],
+        };
+        let secret = |name: &str| std::env::var(name).ok()
+            .or_else(|| file.iter().find(|(key, _)| key == name).map(|(_, value)| value.clone()))
+            .filter(|value| !value.is_empty());
+
+        Ok(BackendAuth {
+            token: secret(TOKEN_VAR),
+            signing_key: secret(SIGNING_KEY_VAR).map(String::into_bytes),
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.token.is_none() && self.signing_key.is_none()
+    }
+
+    /// Headers for a request carrying `body`, exactly as it will be sent
+    pub fn headers(&self, body: &[u8]) -> Vec<(String, String)> {
+        let mut headers = vec![];
+        if let Some(token) = &self.token {
+            headers.push((String::from("Authorization"), format!("Bearer {}", token)));
+        }
+        if let Some(key) = &self.signing_key {
+            let timestamp = Utc::now().timestamp();
+            headers.push((String::from(TIMESTAMP_HEADER), timestamp.to_string()));
+            headers.push((String::from(SIGNATURE_HEADER), format!("sha256={}", sign(key, timestamp, body))));
+        }
+        headers
+    }
+}
+
+fn parse_secrets(contents: &str) -> Vec<(String, String)> {
+    contents.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().trim_matches('"').to_string()))
+        .collect()
+}
+
+fn mac(key: &[u8], timestamp: i64, body: &[u8]) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any size");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    mac
+}
+
+/// Hex HMAC-SHA256 of `"<timestamp>.<body>"`
+pub fn sign(key: &[u8], timestamp: i64, body: &[u8]) -> String {
+    hex::encode(mac(key, timestamp, body).finalize().into_bytes())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum VerifyError {
+    #[error("missing or malformed timestamp")]
+    BadTimestamp,
+    #[error("timestamp is {0} seconds away from now")]
+    Stale(i64),
+    #[error("missing or malformed signature")]
+    BadSignature,
+    #[error("signature does not match the body")]
+    Mismatch,
+}
+
+/* For the receiving side: checks the `X-Aragog-Timestamp` and
+ * `X-Aragog-Signature` headers of a request against its raw body. Requests
+ * signed more than `tolerance_secs` away from `now` are refused, so a captured
+ * request can not be replayed later on.
+ */
+pub fn verify(key: &[u8], timestamp: &str, signature: &str, body: &[u8], now: DateTime<Utc>, tolerance_secs: i64) -> Result<(), VerifyError> {
+    let timestamp: i64 = timestamp.trim().parse().map_err(|_| VerifyError::BadTimestamp)?;
+    // Timestamps that far away do not even fit the difference
+    let skew = now.timestamp().checked_sub(timestamp).ok_or(VerifyError::BadTimestamp)?;
+    if skew.unsigned_abs() > tolerance_secs.unsigned_abs() {
+        return Err(VerifyError::Stale(skew));
+    }
+
+    let signature = signature.trim().strip_prefix("sha256=").ok_or(VerifyError::BadSignature)?;
+    let signature = hex::decode(signature).map_err(|_| VerifyError::BadSignature)?;
+    // Constant time comparison
+    mac(key, timestamp, body).verify_slice(&signature).map_err(|_| VerifyError::Mismatch)
+}
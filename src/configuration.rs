@@ -1,13 +1,53 @@
+use std::collections::BTreeMap;
+use crate::types::{Availability, Condition, Currency, ProductType};
+
 #[derive(serde::Deserialize)]
 pub struct Settings {
     pub backend: BackendSettings,
     pub telemetry: TelemetrySettings,
+    #[serde(default)]
+    pub shops: Vec<ShopSettings>,
+    /// Rules applied to the names of every shop, see `rules::NameRules`
+    #[serde(default)]
+    pub name_rules: Vec<NameRuleSettings>,
+    /// Product types sent on, entries of any other type are rejected
+    #[serde(default = "default_publish_types")]
+    pub publish_types: Vec<ProductType>,
+    /// Availability texts of every shop, see `rules::AvailabilityMap`
+    #[serde(default)]
+    pub availability: Vec<AvailabilitySettings>,
+    #[serde(default)]
+    pub concurrency: ConcurrencySettings,
+    #[serde(default)]
+    pub report: ReportSettings,
+    #[serde(default)]
+    pub output: OutputSettings,
+    #[serde(default)]
+    pub store: StoreSettings,
+    #[serde(default)]
+    pub outbox: OutboxSettings,
+    #[serde(default)]
+    pub webhooks: Vec<WebhookSettings>,
 }
 
 #[derive(serde::Deserialize)]
 pub struct BackendSettings {
     pub url: String,
     pub ep: String,
+    /// Endpoint taking arrays of offers, see `sink::BulkBackendSink`. Offers
+    /// go one by one to `ep` when missing.
+    #[serde(default)]
+    pub bulk_ep: Option<String>,
+    /// Offers per bulk request
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+    /// Longest an offer waits for its batch to fill up
+    #[serde(default = "default_batch_wait_secs")]
+    pub batch_wait_secs: u64,
+    /// File with the backend credentials, see `auth::BackendAuth::load`. The
+    /// credentials themselves never go in this file.
+    #[serde(default)]
+    pub secrets_file: Option<String>,
 }
 
 #[derive(serde::Deserialize)]
@@ -16,10 +56,348 @@ pub struct TelemetrySettings {
     pub service_name: String,
 }
 
+#[derive(serde::Deserialize)]
+pub struct ConcurrencySettings {
+    /// Requests in flight at once across all shops and the backend
+    pub global: usize,
+}
+
+impl Default for ConcurrencySettings {
+    fn default() -> Self {
+        ConcurrencySettings { global: 8 }
+    }
+}
+
+/// When a run counts as failed, checked shop by shop at the end of the run
+#[derive(serde::Deserialize)]
+pub struct ReportSettings {
+    /// Failures allowed per entry seen, from 0 to 1
+    pub max_failure_rate: f64,
+    /// Offers the backend must have taken from every shop
+    pub min_offers_posted: usize,
+    /// Backend timeouts (408) allowed, unlimited when missing
+    #[serde(default)]
+    pub max_timeouts: Option<usize>,
+}
+
+impl Default for ReportSettings {
+    fn default() -> Self {
+        ReportSettings {
+            max_failure_rate: 0.5,
+            min_offers_posted: 1,
+            max_timeouts: None,
+        }
+    }
+}
+
+/// Where offers go instead of the backend, overridden by `--output` and
+/// `--format`
+#[derive(serde::Deserialize, Default)]
+pub struct OutputSettings {
+    /// File to write the offers into, the backend is used when missing
+    #[serde(default)]
+    pub path: Option<String>,
+    /// Guessed from the extension of `path` when missing
+    #[serde(default)]
+    pub format: Option<OutputFormat>,
+}
+
+#[derive(serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputFormat {
+    JsonLines,
+    Csv,
+}
+
+impl OutputFormat {
+    /// CSV for `.csv` files, JSON Lines for anything else
+    pub fn for_path(path: &str) -> OutputFormat {
+        if path.to_lowercase().ends_with(".csv") {
+            OutputFormat::Csv
+        } else {
+            OutputFormat::JsonLines
+        }
+    }
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "jsonl" | "json_lines" => Ok(OutputFormat::JsonLines),
+            "csv" => Ok(OutputFormat::Csv),
+            _ => Err(format!("Unknown output format `{}`, use `jsonl` or `csv`", s)),
+        }
+    }
+}
+
+/// Local price history, see `store::Store`
+#[derive(serde::Deserialize, Default)]
+pub struct StoreSettings {
+    /// SQLite database, no history is kept when missing
+    #[serde(default)]
+    pub path: Option<String>,
+}
+
+/// Offers the backend failed to take, see `outbox::Outbox`
+#[derive(serde::Deserialize)]
+pub struct OutboxSettings {
+    pub dir: String,
+    /// Entries older than this are given up on
+    pub max_age_hours: i64,
+}
+
+impl Default for OutboxSettings {
+    fn default() -> Self {
+        OutboxSettings {
+            dir: String::from("outbox"),
+            max_age_hours: 72,
+        }
+    }
+}
+
+/// Where to notify of interesting offers, see `sink::WebhookSink`
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct WebhookSettings {
+    pub id: String,
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Environment variable with the URL, for webhooks with a token in it
+    #[serde(default)]
+    pub url_env: Option<String>,
+    /// JSON body with `{{field}}` placeholders
+    pub template: String,
+    /// Shop ids to notify about, all of them when empty
+    #[serde(default)]
+    pub shops: Vec<String>,
+    /// Product types to notify about, all of them when empty
+    #[serde(default)]
+    pub product_types: Vec<ProductType>,
+    /// Minimum percentage off the normal price
+    #[serde(default)]
+    pub min_discount: u32,
+    #[serde(default = "default_webhook_retries")]
+    pub retries: usize,
+    /// Grows with every retry
+    #[serde(default = "default_webhook_retry_delay_secs")]
+    pub retry_delay_secs: u64,
+}
+
+/// Declarative description of a PrestaShop-like store, see `SelectorShopParser`.
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct ShopSettings {
+    /// Identifier used from the command line
+    pub id: String,
+    /// Name sent to the backend as `shop_name`
+    pub name: String,
+    /// Parser used for this shop, see `parser::ShopRegistry`
+    #[serde(default = "default_shop_kind")]
+    pub kind: String,
+    pub start_urls: Vec<String>,
+    /// Entries listed on each page, used to turn `--limit` into pages
+    pub page_size: i32,
+    /// Time to wait before retrying a listing page that failed
+    #[serde(default)]
+    pub retry_delay_secs: u64,
+    /// Assumed when prices come without a currency symbol
+    #[serde(default)]
+    pub currency: Currency,
+    /// Requests in flight at once against this shop
+    #[serde(default = "default_shop_concurrency")]
+    pub concurrency: usize,
+    pub selectors: SelectorSettings,
+    /// Extra name rules for this shop, merged with the global ones
+    #[serde(default)]
+    pub name_rules: Vec<NameRuleSettings>,
+    /// Availability texts of this shop, looked up before the global ones
+    #[serde(default)]
+    pub availability: Vec<AvailabilitySettings>,
+    #[serde(default)]
+    pub truncated_name: Option<TruncatedNameSettings>,
+    /// Where to find the product details, for runs with `--details`
+    #[serde(default)]
+    pub details: DetailSettings,
+}
+
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct SelectorSettings {
+    /// Element wrapping every product of the listing
+    pub container: String,
+    /// Containers without a name are layout filler, not broken products, so
+    /// they are dropped instead of counted as failures
+    #[serde(default)]
+    pub skip_unnamed: bool,
+    pub name: FieldSettings,
+    pub url: FieldSettings,
+    pub offer_price: FieldSettings,
+    /// When missing, the product is not discounted and `offer_price` is used
+    #[serde(default)]
+    pub normal_price: Option<FieldSettings>,
+    #[serde(default)]
+    pub availability: Option<FieldSettings>,
+    /// Link to the next listing page, looked up in the whole document
+    pub next_page: FieldSettings,
+}
+
+/// How to get a single value out of an element.
+///
+/// By default the text of the first element matching `selector` is used.
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct FieldSettings {
+    pub selector: String,
+    /// Read this attribute instead of the text
+    #[serde(default)]
+    pub attr: Option<String>,
+    /// Only keep the first text node of the element
+    #[serde(default)]
+    pub first_text: bool,
+    /// Fixed value when the selector matches, for flags such as "sold out"
+    #[serde(default)]
+    pub value: Option<String>,
+    /// Value when the selector does not match
+    #[serde(default)]
+    pub default: Option<String>,
+    /// Drop anything that is not alphanumeric or whitespace
+    #[serde(default)]
+    pub alphanumeric: bool,
+}
+
+/// Some shops shorten long names with a marker such as "...".
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct TruncatedNameSettings {
+    pub marker: String,
+    /// Selector for the full name in the product page. Without it truncated
+    /// entries are skipped.
+    #[serde(default)]
+    pub detail_selector: Option<String>,
+    /// Courtesy delay before fetching the full name. In runs with
+    /// `--details` the longer of this and `details.delay_secs` is used.
+    #[serde(default)]
+    pub detail_delay_secs: u64,
+}
+
+/// Extra product data only found in the product page
+#[derive(serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum DetailField {
+    Ean,
+    Sku,
+    Publisher,
+    Players,
+    Playtime,
+    AgeRating,
+    Image,
+}
+
+/* How to read the product page of a shop. Every detail comes from, in order:
+ *  - its entry in `fields`, looked up in the whole page
+ *  - the row of the `features` table with its label
+ *  - the schema.org Product in the JSON-LD of the page, for EAN, SKU,
+ *    publisher and image
+ * so shops with good JSON-LD need no settings at all.
+ */
+#[derive(serde::Deserialize, Debug, Clone, Default)]
+pub struct DetailSettings {
+    /// Wait before each product page of a `--details` run. It is the same
+    /// page truncated names are recovered from, so those wait the longer of
+    /// this and `truncated_name.detail_delay_secs`, never both.
+    #[serde(default)]
+    pub delay_secs: u64,
+    #[serde(default)]
+    pub fields: BTreeMap<DetailField, FieldSettings>,
+    #[serde(default)]
+    pub features: Option<FeatureTableSettings>,
+}
+
+/// Name/value table of a product page, such as the PrestaShop data sheet.
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct FeatureTableSettings {
+    /// Feature names, in document order
+    pub name: String,
+    /// Feature values, paired with the names by position
+    pub value: String,
+    /// Name of the feature holding each detail, case does not matter
+    pub labels: BTreeMap<DetailField, String>,
+}
+
+/// Availability texts that mean `status`
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct AvailabilitySettings {
+    pub status: Availability,
+    pub values: Vec<String>,
+}
+
+#[derive(serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleAction {
+    /// Drop the entry altogether
+    Reject,
+    /// Remove the matched text from the name
+    Strip,
+    /// Replace the matched text with `replacement`
+    Replace,
+    /// Leave the name alone, just set the language, condition or product type
+    Tag,
+}
+
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct NameRuleSettings {
+    /// Recorded in the offer (or rejection) whenever the rule fires
+    pub id: String,
+    pub action: RuleAction,
+    pub pattern: String,
+    #[serde(default)]
+    pub replacement: String,
+    /// Language of the edition, such as "es", when the rule fires
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Condition of the item when the rule fires
+    #[serde(default)]
+    pub condition: Option<Condition>,
+    /// Product type of the entry when the rule fires
+    #[serde(default)]
+    pub product_type: Option<ProductType>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_publish_types() -> Vec<ProductType> {
+    ProductType::ALL.to_vec()
+}
+
+fn default_batch_size() -> usize {
+    50
+}
+
+fn default_batch_wait_secs() -> u64 {
+    2
+}
+
+fn default_webhook_retries() -> usize {
+    3
+}
+
+fn default_webhook_retry_delay_secs() -> u64 {
+    2
+}
+
+fn default_shop_kind() -> String {
+    String::from("selector")
+}
+
+fn default_shop_concurrency() -> usize {
+    2
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
 pub fn get_configuration() -> Result<Settings, config::ConfigError> {
     let mut settings = config::Config::default();
 
     settings.merge(config::File::with_name("configuration"))?;
 
     settings.try_into()
-}
\ No newline at end of file
+}
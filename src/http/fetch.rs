@@ -0,0 +1,52 @@
+use async_trait::async_trait;
+use crate::parser::ScrapeError;
+
+/// A page as the shop served it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FetchedPage {
+    pub status: u16,
+    pub body: String,
+}
+
+/// Where parsers get their pages from, and where offers get posted to.
+/// Usually the network, but tests and replays can serve them from anywhere
+/// else.
+#[async_trait]
+pub trait Fetch: Send + Sync {
+    async fn fetch(&self, url: &str) -> Result<FetchedPage, ScrapeError>;
+
+    /// Sends `body` as `serde_json::to_vec` writes it, so it can be signed
+    /// beforehand
+    async fn post_json(&self, url: &str, body: &serde_json::Value, headers: &[(String, String)]) -> Result<FetchedPage, ScrapeError>;
+}
+
+#[async_trait]
+impl Fetch for reqwest::Client {
+    async fn fetch(&self, url: &str) -> Result<FetchedPage, ScrapeError> {
+        let response = self
+            .get(url)
+            .timeout(std::time::Duration::from_secs(600))
+            .send()
+            .await?;
+        let status = response.status().as_u16();
+        let body = response.text().await?;
+
+        Ok(FetchedPage { status, body })
+    }
+
+    async fn post_json(&self, url: &str, body: &serde_json::Value, headers: &[(String, String)]) -> Result<FetchedPage, ScrapeError> {
+        let body = serde_json::to_vec(body).map_err(|e| ScrapeError::Http(e.to_string()))?;
+        let mut request = self
+            .post(url)
+            .header("Content-Type", "application/json")
+            .timeout(std::time::Duration::from_secs(600));
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+        let response = request.body(body).send().await?;
+        let status = response.status().as_u16();
+        let body = response.text().await?;
+
+        Ok(FetchedPage { status, body })
+    }
+}
@@ -0,0 +1,5 @@
+mod fetch;
+mod record;
+
+pub use fetch::{Fetch, FetchedPage};
+pub use record::{Recorder, Replayer};
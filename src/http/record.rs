@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use crate::http::{Fetch, FetchedPage};
+use crate::parser::ScrapeError;
+
+/// One request and what came back, as saved by `Recorder`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Exchange {
+    method: String,
+    url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    request: Option<serde_json::Value>,
+    #[serde(default)]
+    status: u16,
+    #[serde(default)]
+    body: String,
+    /// Set when there was no response at all
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl Exchange {
+    fn into_result(self) -> Result<FetchedPage, ScrapeError> {
+        match self.error {
+            Some(error) => Err(ScrapeError::Http(error)),
+            None => Ok(FetchedPage { status: self.status, body: self.body }),
+        }
+    }
+}
+
+/* Every request is saved as `<method>-<url>-<hash>-<n>.json`, where `n` counts
+ * the times the same request was made (retries, mostly) so a replay goes
+ * through them in the same order. Fields that change on every run, like the
+ * trace context or the batch, run and time of a posted offer, are left out of
+ * the hash.
+ *
+ * Bulk posts hash the whole array, and which offers end up together depends
+ * on the timing of the run. A replay of a bulk run rarely batches them the same
+ * way, so its posts are not found; record and replay with single posts.
+ */
+const VOLATILE_FIELDS: &[&str] = &["context", "batch", "run_id", "scraped_at"];
+
+fn without_volatile_fields(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(fields) => {
+            fields.retain(|name, _| !VOLATILE_FIELDS.contains(&name.as_str()));
+            fields.values_mut().for_each(without_volatile_fields);
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(without_volatile_fields),
+        _ => (),
+    }
+}
+
+fn exchange_name(method: &str, url: &str, request: Option<&serde_json::Value>) -> String {
+    let mut request = request.cloned();
+    if let Some(request) = request.as_mut() {
+        without_volatile_fields(request);
+    }
+    let key = format!("{} {} {}", method, url, request.map(|r| r.to_string()).unwrap_or_default());
+
+    // FNV-1a, stable across builds unlike the std hasher
+    let hash = key.bytes().fold(0xcbf29ce484222325u64, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3));
+
+    let readable: String = url.trim_start_matches("https://").trim_start_matches("http://")
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .take(80)
+        .collect();
+    format!("{}-{}-{:016x}", method, readable, hash)
+}
+
+// Hands out 0, 1, 2... for every exchange name
+#[derive(Debug, Default)]
+struct Counters(Mutex<HashMap<String, usize>>);
+
+impl Counters {
+    fn next(&self, name: &str) -> usize {
+        let mut counters = self.0.lock().unwrap();
+        let counter = counters.entry(name.to_string()).or_default();
+        *counter += 1;
+        *counter - 1
+    }
+}
+
+/// Goes to the network through `inner` and saves every exchange in `dir`
+pub struct Recorder<F> {
+    inner: F,
+    dir: PathBuf,
+    counters: Counters,
+    unsaved: Vec<String>,
+}
+
+impl<F: Fetch> Recorder<F> {
+    pub fn new(inner: F, dir: &Path) -> Result<Recorder<F>, std::io::Error> {
+        std::fs::create_dir_all(dir)?;
+        Ok(Recorder { inner, dir: dir.to_path_buf(), counters: Counters::default(), unsaved: vec![] })
+    }
+
+    /// Requests to any of `urls` still go out, but nothing about them is
+    /// saved. For URLs with a secret in them, such as webhooks.
+    pub fn never_save(mut self, urls: Vec<String>) -> Recorder<F> {
+        self.unsaved = urls;
+        self
+    }
+
+    async fn save(&self, method: &str, url: &str, request: Option<&serde_json::Value>, result: &Result<FetchedPage, ScrapeError>) {
+        if self.unsaved.iter().any(|unsaved| unsaved == url) {
+            return;
+        }
+        let name = exchange_name(method, url, request);
+        let path = self.dir.join(format!("{}-{}.json", name, self.counters.next(&name)));
+        let exchange = Exchange {
+            method: method.to_string(),
+            url: url.to_string(),
+            request: request.cloned(),
+            status: result.as_ref().map(|page| page.status).unwrap_or_default(),
+            body: result.as_ref().map(|page| page.body.clone()).unwrap_or_default(),
+            error: result.as_ref().err().map(|e| match e {
+                ScrapeError::Http(message) => message.clone(),
+                e => e.to_string(),
+            }),
+        };
+
+        // A missing recording should not ruin the run being recorded
+        let saved = match serde_json::to_vec_pretty(&exchange) {
+            Ok(contents) => tokio::fs::write(&path, contents).await.map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        if let Err(e) = saved {
+            tracing::error!("Unable to record {} into {}: {}", url, path.display(), e);
+        }
+    }
+}
+
+#[async_trait]
+impl<F: Fetch> Fetch for Recorder<F> {
+    async fn fetch(&self, url: &str) -> Result<FetchedPage, ScrapeError> {
+        let result = self.inner.fetch(url).await;
+        self.save("GET", url, None, &result).await;
+        result
+    }
+
+    // Headers carry credentials, they are sent but never saved
+    async fn post_json(&self, url: &str, body: &serde_json::Value, headers: &[(String, String)]) -> Result<FetchedPage, ScrapeError> {
+        let result = self.inner.post_json(url, body, headers).await;
+        self.save("POST", url, Some(body), &result).await;
+        result
+    }
+}
+
+/// Serves the exchanges saved by `Recorder` without touching the network.
+/// A request made more times than recorded gets the last answer again.
+pub struct Replayer {
+    dir: PathBuf,
+    counters: Counters,
+}
+
+impl Replayer {
+    pub fn new(dir: &Path) -> Result<Replayer, std::io::Error> {
+        if !dir.is_dir() {
+            return Err(std::io::Error::new(std::io::ErrorKind::NotFound, format!("{} is not a directory", dir.display())));
+        }
+        Ok(Replayer { dir: dir.to_path_buf(), counters: Counters::default() })
+    }
+
+    async fn load(&self, method: &str, url: &str, request: Option<&serde_json::Value>) -> Result<FetchedPage, ScrapeError> {
+        let name = exchange_name(method, url, request);
+        let mut n = self.counters.next(&name);
+        loop {
+            let path = self.dir.join(format!("{}-{}.json", name, n));
+            match tokio::fs::read(&path).await {
+                Ok(contents) => {
+                    let exchange: Exchange = serde_json::from_slice(&contents)
+                        .map_err(|e| ScrapeError::Http(format!("Bad recording {}: {}", path.display(), e)))?;
+                    return exchange.into_result();
+                }
+                Err(_) if n > 0 => n -= 1,
+                Err(_) => return Err(ScrapeError::Http(format!("{} {} was not recorded", method, url))),
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Fetch for Replayer {
+    async fn fetch(&self, url: &str) -> Result<FetchedPage, ScrapeError> {
+        self.load("GET", url, None).await
+    }
+
+    async fn post_json(&self, url: &str, body: &serde_json::Value, _: &[(String, String)]) -> Result<FetchedPage, ScrapeError> {
+        self.load("POST", url, Some(body)).await
+    }
+}
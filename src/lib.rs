@@ -1,4 +1,11 @@
 pub mod parser;
 pub mod types;
+pub mod sink;
+pub mod rules;
+pub mod report;
+pub mod store;
+pub mod outbox;
+pub mod auth;
+pub mod http;
 pub mod configuration;
 pub mod telemetry;
\ No newline at end of file
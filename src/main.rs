@@ -1,9 +1,23 @@
-use color_eyre::Report;
-use tracing::info;
-use aragog::configuration::get_configuration;
-use aragog::parser::{Configuration, DracotiendaParser, ShopParser, JugamosotraParser, DungeonMarvelsParser};
+use color_eyre::{eyre::eyre, Report};
+use aragog::configuration::{get_configuration, BackendSettings, OutboxSettings, OutputFormat, Settings};
+use aragog::http::{Fetch, Recorder, Replayer};
+use aragog::parser::{ParserContext, RegisteredShop, ScrapeStats, ShopParser, ShopRegistry};
+use aragog::sink::{BackendSink, BulkBackendSink, ConsoleSink, CsvSink, FanOutSink, JsonLinesSink, OfferSink, OutboxSink, UnmatchedSink, WebhookSink};
+use aragog::outbox::Outbox;
+use aragog::auth::BackendAuth;
+use aragog::store::Store;
+use aragog::types::ScrapeEvent;
+use aragog::report::{ReportFormat, RunSummary};
 use aragog::telemetry::init_telemetry;
 use argh::FromArgs;
+use chrono::Utc;
+use futures::future::join_all;
+use std::path::Path;
+use std::process::ExitCode;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{mpsc::Sender, Semaphore};
+use tokio::task::JoinHandle;
 
 #[derive(FromArgs)]
 /// Reach new heights.
@@ -12,75 +26,389 @@ struct AppParams {
     #[argh(option, default = "70")]
     limit: i32,
 
-    /// which shop to analyze, can be `all` for all of them to run
+    /// comma separated shops to analyze, `all` for all of them. Prefix a shop
+    /// with `-` to leave it out, as in `all,-jugamosotra`
     #[argh(option, default = "String::from(\"all\")")]
-    shop: String
+    shop: String,
+
+    /// print the available shops and exit
+    #[argh(switch)]
+    list_shops: bool,
+
+    /// how to print the summary at the end of the run, `table` or `json`
+    #[argh(option, default = "ReportFormat::Table")]
+    report: ReportFormat,
+
+    /// save every request of the run, to shops and backend alike, into this
+    /// directory
+    #[argh(option)]
+    record: Option<String>,
+
+    /// answer every request with what was saved by `--record` into this
+    /// directory, without touching the network. The outbox and the configured
+    /// store are left alone, use --store to keep the offers
+    #[argh(option)]
+    replay: Option<String>,
+
+    /// write the offers into this file instead of posting them to the
+    /// backend
+    #[argh(option)]
+    output: Option<String>,
+
+    /// format of the --output file, `jsonl` or `csv`. Guessed from the file
+    /// extension when missing
+    #[argh(option)]
+    format: Option<OutputFormat>,
+
+    /// keep the history of every offer in this SQLite database, on top of
+    /// publishing it
+    #[argh(option)]
+    store: Option<String>,
+
+    /// scrape as usual but only print the offers, rejected ones included,
+    /// without publishing or storing anything
+    #[argh(switch)]
+    dry_run: bool,
+
+    /// stream the offers to stdout as JSON lines instead of posting them, with
+    /// logs and summary on stderr
+    #[argh(switch)]
+    stdout: bool,
+
+    /// visit the page of every product for its EAN, SKU, publisher, players,
+    /// playtime, age rating and image. Slower and heavier on the shops.
+    #[argh(switch)]
+    details: bool,
+
+    #[argh(subcommand)]
+    command: Option<Command>,
 }
 
+#[derive(FromArgs)]
+#[argh(subcommand)]
+enum Command {
+    FlushOutbox(FlushOutbox),
+    Unmatched(Unmatched),
+}
 
-// Helper macro, just for the sake of learning
-macro_rules! shop {
-    // `()` indicates that the macro takes no argument.
-    ($thread_vector:ident, $parser:ty, $url:literal, $limit:expr) => {
+#[derive(FromArgs)]
+/// post the offers left in the outbox by previous runs, then exit
+#[argh(subcommand, name = "flush-outbox")]
+struct FlushOutbox {}
 
-        $thread_vector.push(std::thread::spawn(move || {
-            // TODO: Super sub-optimal, reading the config file several times
-            // Read configuration
-            let configuration = get_configuration().expect("Failed to read configuration file");
-            let cfg = Configuration {
-                server_address: String::from(configuration.backend.url.clone()),
-                post_endpoint: String::from(configuration.backend.ep.clone()),
+#[derive(FromArgs)]
+/// list the names the backend failed to match most often, then exit
+#[argh(subcommand, name = "unmatched")]
+struct Unmatched {
+    /// how many names to list
+    #[argh(option, default = "20")]
+    top: usize,
+}
+
+// Offers the backend can not match are kept for review when there is a store
+fn review_unmatched(sink: Box<dyn OfferSink>, store: &Option<Store>) -> Box<dyn OfferSink> {
+    match store {
+        Some(store) => Box::new(UnmatchedSink::new(sink, store.clone())),
+        None => sink,
+    }
+}
+
+// Credentials and outbox are only needed when talking to the backend
+fn load_auth(backend: &BackendSettings) -> Result<BackendAuth, Report> {
+    let auth = BackendAuth::load(backend.secrets_file.as_deref().map(Path::new))?;
+    if auth.is_empty() {
+        tracing::warn!("No backend credentials found, posting without them");
+    }
+    Ok(auth)
+}
+
+fn open_outbox(outbox: &OutboxSettings) -> Result<Arc<Outbox>, Report> {
+    Ok(Arc::new(Outbox::open(Path::new(&outbox.dir), chrono::Duration::hours(outbox.max_age_hours))?))
+}
+
+// Where offers go: the store, the output file or the backend, and webhooks
+async fn build_sink(
+    up: &AppParams,
+    configuration: &Settings,
+    registry: &ShopRegistry,
+    store: &Option<Store>,
+    client: &Arc<dyn Fetch>,
+    global_limit: &Arc<Semaphore>,
+) -> Result<Arc<dyn OfferSink>, Report> {
+    let backend = &configuration.backend;
+    let mut sinks: Vec<Box<dyn OfferSink>> = vec![];
+    if let Some(store) = store {
+        sinks.push(Box::new(store.clone()));
+    }
+    let output = up.output.as_ref().or(configuration.output.path.as_ref());
+    sinks.push(match output {
+        _ if up.dry_run => Box::new(ConsoleSink::stdout()),
+        _ if up.stdout => Box::new(JsonLinesSink::stdout()),
+        Some(path) => match up.format.or(configuration.output.format).unwrap_or_else(|| OutputFormat::for_path(path)) {
+            OutputFormat::JsonLines => Box::new(JsonLinesSink::new(Path::new(path))?),
+            OutputFormat::Csv => Box::new(CsvSink::new(Path::new(path))?),
+        },
+        None => {
+            // Offers the backend failed to take in previous runs go first. Replays
+            // post to the recording, so they leave the outbox alone.
+            let auth = load_auth(backend)?;
+            let outbox = match up.replay {
+                Some(_) => None,
+                None => {
+                    let outbox = open_outbox(&configuration.outbox)?;
+                    let single = BackendSink::new(&backend.url, &backend.ep, client.clone(), global_limit.clone(), auth.clone());
+                    // Whatever is not drained stays for the next run
+                    if let Err(e) = outbox.drain(review_unmatched(Box::new(single), store).as_ref()).await {
+                        tracing::error!("Unable to drain the outbox: {}", e);
+                    }
+                    Some(outbox)
+                }
             };
-            let parser = <$parser>::new(cfg);
-            let _ = parser.process(&reqwest::blocking::Client::new(), $url, $limit);
-        }));
-    };
+            let single = BackendSink::new(&backend.url, &backend.ep, client.clone(), global_limit.clone(), auth);
+            let backend_sink: Box<dyn OfferSink> = match &backend.bulk_ep {
+                Some(bulk_ep) => {
+                    // Which offers share a batch depends on timing, so the bulk posts of a replay rarely match the recorded ones
+                    if up.record.is_some() || up.replay.is_some() {
+                        tracing::warn!("Bulk posts can not be replayed reliably, leave backend.bulk_ep unset to record or replay the backend");
+                    }
+                    Box::new(BulkBackendSink::new(single, bulk_ep, backend.batch_size, Duration::from_secs(backend.batch_wait_secs)))
+                }
+                None => Box::new(single),
+            };
+            let backend_sink = review_unmatched(backend_sink, store);
+            match outbox {
+                Some(outbox) => Box::new(OutboxSink::new(backend_sink, outbox)),
+                None => backend_sink,
+            }
+        }
+    });
+    // Replays do not notify anybody, the offers are not new
+    for webhook in configuration.webhooks.iter().filter(|_| !up.dry_run && up.replay.is_none()) {
+        let mut shop_names = vec![];
+        for id in &webhook.shops {
+            match registry.get(id) {
+                Some(shop) => shop_names.push(shop.settings.name.clone()),
+                None => return Err(eyre!("Webhook `{}` refers to unknown shop `{}`", webhook.id, id)),
+            }
+        }
+        sinks.push(Box::new(WebhookSink::new(webhook.clone(), shop_names, client.clone(), global_limit.clone())?));
+    }
+    Ok(Arc::new(FanOutSink::new(sinks)))
+}
+
+// Scrapes every start URL of a shop, one after the other
+fn spawn_shop(shop: &RegisteredShop, parser: Box<dyn ShopParser>, client: Arc<dyn Fetch>, limit: i32, offers: Sender<ScrapeEvent>) -> JoinHandle<(String, ScrapeStats)> {
+    let shop_name = shop.settings.name.clone();
+    let start_urls = shop.settings.start_urls.clone();
+
+    tokio::spawn(async move {
+        let mut stats = ScrapeStats::default();
+        for url in start_urls {
+            match parser.process(client.as_ref(), &url, limit, &offers).await {
+                Ok(url_stats) => stats.merge(&url_stats),
+                // Whatever was scraped from this URL is lost, but not the fact
+                Err(e) => {
+                    tracing::error!("{}", e);
+                    *stats.failures.entry(String::from("aborted")).or_default() += 1;
+                }
+            }
+        }
+        (shop_name, stats)
+    })
 }
 
 #[tokio::main]
-async fn main() -> Result<(), Report> {
+async fn main() -> Result<ExitCode, Report> {
     //setup()?;
 
-    // Setup telemetry
-    let configuration = get_configuration().expect("Failed to read configuration file");
-    init_telemetry(&configuration.telemetry.endpoint, &configuration.telemetry.service_name);
-
     // Argument parsing
     let up: AppParams = argh::from_env();
-    
-    // Accumulate children
-    let mut children = vec![];
-
-    // Note the Domain Specific Language - ish macros
-    match up.shop.as_str() {
-        "all" => {
-            shop!(children, DracotiendaParser, "https://dracotienda.com/1715-juegos-de-tablero", up.limit);
-            shop!(children, JugamosotraParser, "https://jugamosotra.com/es/24-juegos?order=product.sales.desc", up.limit);
-            shop!(children, DungeonMarvelsParser, "https://dungeonmarvels.com/10-juegos-de-tablero", up.limit);
+
+    // Setup telemetry, away from stdout when offers go there
+    let configuration = get_configuration().expect("Failed to read configuration file");
+    init_telemetry(&configuration.telemetry.endpoint, &configuration.telemetry.service_name, up.stdout || up.dry_run);
+
+    if up.stdout && (up.output.is_some() || up.dry_run) {
+        tracing::error!("--stdout can not be used together with --output or --dry-run");
+        return Ok(ExitCode::FAILURE);
+    }
+
+    // Nothing else is needed to tell the shops
+    let registry = ShopRegistry::new(&configuration.shops)?;
+    if up.list_shops {
+        println!("{}", registry.describe());
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // Every request, be it to a shop or to the backend, needs one of these
+    let global_limit = Arc::new(Semaphore::new(configuration.concurrency.global.max(1)));
+    let client: Arc<dyn Fetch> = match (&up.record, &up.replay) {
+        (Some(_), Some(_)) => {
+            tracing::error!("--record and --replay can not be used together");
+            return Ok(ExitCode::FAILURE);
         }
-        "dracotienda" => {
-            shop!(children, DracotiendaParser, "https://dracotienda.com/1715-juegos-de-tablero", up.limit);
+        (Some(dir), None) => {
+            // Webhook URLs usually carry a token, better not to write them down
+            let webhooks = configuration.webhooks.iter().filter_map(|webhook| WebhookSink::url(webhook).ok()).collect();
+            Arc::new(Recorder::new(reqwest::Client::new(), Path::new(dir))?.never_save(webhooks))
         }
-        "jugamosotra" => {
-            shop!(children, JugamosotraParser, "https://jugamosotra.com/es/24-juegos?order=product.sales.desc", up.limit);
+        (None, Some(dir)) => Arc::new(Replayer::new(Path::new(dir))?),
+        (None, None) => Arc::new(reqwest::Client::new()),
+    };
+    // Tells apart the offers of different runs
+    let run_id = format!("{}-{}", Utc::now().format("%Y%m%dT%H%M%S"), std::process::id());
+
+    // The store is only opened when it is going to be used, opening creates it.
+    // Replays only write into one given with --store, never the usual one.
+    let store_path = match up.replay {
+        Some(_) => up.store.as_ref(),
+        None => up.store.as_ref().or(configuration.store.path.as_ref()),
+    }.map(Path::new);
+    if let Some(Command::Unmatched(Unmatched { top })) = up.command {
+        let Some(path) = store_path else {
+            tracing::error!("There is no store to read from, set `store.path` or use --store");
+            return Ok(ExitCode::FAILURE);
+        };
+        let store = Store::open(path)?;
+        println!("{:>6}  {:>5}  {:<50}  example", "times", "shops", "name");
+        for title in store.top_unmatched(top)? {
+            println!("{:>6}  {:>5}  {:<50}  {}", title.times_seen, title.shops, title.name, title.raw_name);
+        }
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // Offers the backend failed to take in previous runs
+    let backend = &configuration.backend;
+    if let Some(Command::FlushOutbox(_)) = up.command {
+        if up.replay.is_some() {
+            tracing::error!("The outbox holds real offers, it can not be flushed against a replay");
+            return Ok(ExitCode::FAILURE);
         }
-        "dungeonmarvels" => {
-            shop!(children, DungeonMarvelsParser, "https://dungeonmarvels.com/10-juegos-de-tablero", up.limit);
+        let auth = load_auth(backend)?;
+        let outbox = open_outbox(&configuration.outbox)?;
+        let store = store_path.map(Store::open).transpose()?;
+        let single = BackendSink::new(&backend.url, &backend.ep, client, global_limit, auth);
+        let report = outbox.drain(review_unmatched(Box::new(single), &store).as_ref()).await?;
+        println!("{}", report);
+        return Ok(if report.pending == 0 { ExitCode::SUCCESS } else { ExitCode::FAILURE });
+    }
+
+    let shops = match registry.select(&up.shop) {
+        Ok(shops) if !shops.is_empty() => shops,
+        Ok(_) => {
+            tracing::error!("No shop left to analyze in `{}`", up.shop);
+            return Ok(ExitCode::FAILURE);
         }
-        &_ => {
-            tracing::error!("Bad option {}", up.shop);
-            return Ok(());
+        Err(e) => {
+            tracing::error!("{}", e);
+            return Ok(ExitCode::FAILURE);
         }
+    };
+    let store = match store_path {
+        Some(path) if !up.dry_run => Some(Store::open(path)?),
+        _ => None,
+    };
+
+    tracing::info!("Starting run {}", run_id);
+
+    let context = ParserContext {
+        name_rules: configuration.name_rules.clone(),
+        availability: configuration.availability.clone(),
+        publish_types: configuration.publish_types.clone(),
+        global_limit: global_limit.clone(),
+        run_id: run_id.clone(),
+        details: up.details,
+    };
+
+    // A bad selector or pattern is a configuration error, better to stop now
+    // than to report a run without the shop
+    let mut parsers = vec![];
+    for shop in &shops {
+        let parser = shop.build(&context).map_err(|e| eyre!("Shop `{}`: {}", shop.settings.id, e))?;
+        parsers.push((shop, parser));
     }
 
+    // Sinks are checked before any shop is visited too: output files, webhook
+    // templates and credentials
+    let sink = build_sink(&up, &configuration, &registry, &store, &client, &global_limit).await?;
+
+    // Publish whatever the parsers find while they are still running
+    let (offers, mut received_offers) = tokio::sync::mpsc::channel(256);
+    let summary = Arc::new(Mutex::new(RunSummary::default()));
+    let publisher_summary = summary.clone();
+    // Sinks take a global permit for every request they make themselves, this
+    // only keeps offers waiting for a batch (or a permit) from piling up
+    let in_flight = Arc::new(Semaphore::new(256.max(configuration.backend.batch_size * 2)));
+    let publisher = tokio::spawn(async move {
+        let mut pending = vec![];
+        while let Some(event) = received_offers.recv().await {
+            let permit = match in_flight.clone().acquire_owned().await {
+                Ok(permit) => permit,
+                Err(_) => break,
+            };
+            let sink = sink.clone();
+            let summary = publisher_summary.clone();
+            pending.push(tokio::spawn(async move {
+                let _permit = permit;
+                let result = match event {
+                    ScrapeEvent::Offer(offer) => {
+                        let delivery = sink.publish(&offer).await;
+                        summary.lock().unwrap().record_delivery(&offer.body().shop_name, &delivery);
+                        delivery.map(|_| ())
+                    }
+                    ScrapeEvent::Rejected(rejection) => sink.reject(&rejection).await,
+                };
+                if let Err(e) = result {
+                    tracing::error!("{}", e);
+                }
+            }));
+        }
+        join_all(pending).await;
+    });
+
+    // Accumulate children, all of them feed the same offer stream
+    let children: Vec<_> = parsers.into_iter()
+        .map(|(shop, parser)| spawn_shop(shop, parser, client.clone(), up.limit, offers.clone()))
+        .collect();
+    drop(offers);
+
     // Wait fot the analysis to finish
-    for child in children {
-        let _ = child.join();
+    for (shop, result) in shops.iter().zip(join_all(children).await) {
+        let (name, stats) = result.unwrap_or_else(|e| {
+            // A shop that panicked still has to show up in the summary
+            tracing::error!("{} crashed: {}", shop.settings.name, e);
+            let mut stats = ScrapeStats::default();
+            stats.failures.insert(String::from("aborted"), 1);
+            (shop.settings.name.clone(), stats)
+        });
+        summary.lock().unwrap().record_scrape(&name, &stats);
+    }
+    let _ = publisher.await;
+
+    // Tell how it went, and make the scheduler notice if it went badly
+    let summary = summary.lock().unwrap().clone();
+    let report = match up.report {
+        ReportFormat::Json => serde_json::to_string_pretty(&summary)?,
+        ReportFormat::Table => summary.to_table(),
+    };
+    match up.stdout {
+        true => eprintln!("{}", report),
+        false => println!("{}", report),
+    }
+
+    let violations = summary.violations(&configuration.report);
+    for violation in &violations {
+        tracing::error!("{}", violation);
+    }
+    if violations.is_empty() {
+        Ok(ExitCode::SUCCESS)
+    } else {
+        Ok(ExitCode::FAILURE)
     }
-    Ok(())
 }
 
 
+#[allow(dead_code)]
 fn setup() -> Result<(), Report> {
     if std::env::var("RUST_LIB_BACKTRACE").is_err() {
         std::env::set_var("RUST_LIB_BACKTRACE", "1")
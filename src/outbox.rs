@@ -0,0 +1,160 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use chrono::{DateTime, Duration, Utc};
+use color_eyre::Report;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+use crate::types::Offer;
+use crate::telemetry::SpannedMessage;
+use crate::sink::OfferSink;
+use crate::parser::ScrapeError;
+
+/// An offer the backend could not take, trace context included
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutboxEntry {
+    pub first_failed_at: DateTime<Utc>,
+    pub last_attempt_at: DateTime<Utc>,
+    pub attempts: usize,
+    pub last_error: String,
+    pub message: SpannedMessage<Offer>,
+}
+
+/// How draining the outbox went
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DrainReport {
+    pub delivered: usize,
+    pub pending: usize,
+    pub dead: usize,
+}
+
+impl std::fmt::Display for DrainReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} delivered, {} still pending, {} sent to the dead letter file", self.delivered, self.pending, self.dead)
+    }
+}
+
+/* Offers that failed for reasons that may go away (no connection, 408, 5xx)
+ * wait here for the next run, one JSON file each. Entries older than `max_age`,
+ * or rejected for good when retried, are appended to `dead-letter.jsonl` in the
+ * same directory and forgotten.
+ */
+pub struct Outbox {
+    dir: PathBuf,
+    max_age: Duration,
+    written: AtomicUsize,
+}
+
+impl Outbox {
+
+    pub fn open(dir: &Path, max_age: Duration) -> Result<Outbox, Report> {
+        std::fs::create_dir_all(dir)?;
+        Ok(Outbox { dir: dir.to_path_buf(), max_age, written: AtomicUsize::new(0) })
+    }
+
+    pub fn push(&self, message: &SpannedMessage<Offer>, error: &str) -> Result<(), Report> {
+        let now = Utc::now();
+        let entry = OutboxEntry {
+            first_failed_at: now,
+            last_attempt_at: now,
+            attempts: 1,
+            last_error: error.to_string(),
+            message: message.clone(),
+        };
+        let name = format!("{}-{}-{}.json", now.format("%Y%m%dT%H%M%S%f"), std::process::id(), self.written.fetch_add(1, Ordering::Relaxed));
+        self.write(&self.dir.join(name), &entry)
+    }
+
+    /// Every entry waiting, oldest first
+    pub fn entries(&self) -> Result<Vec<(PathBuf, OutboxEntry)>, Report> {
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(&self.dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|extension| extension == "json"))
+            .collect();
+        paths.sort();
+
+        let mut entries = vec![];
+        for path in paths {
+            let entry = std::fs::read(&path)
+                .map_err(Report::from)
+                .and_then(|contents| Ok(serde_json::from_slice(&contents)?));
+            match entry {
+                Ok(entry) => entries.push((path, entry)),
+                Err(e) => warn!("Ignoring unreadable outbox entry {}: {}", path.display(), e),
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Publishes every entry through `sink`, one after the other. Once one
+    /// fails for a reason that may go away the rest are not even tried, the
+    /// backend is most likely still down.
+    pub async fn drain(&self, sink: &dyn OfferSink) -> Result<DrainReport, Report> {
+        let mut report = DrainReport::default();
+        let mut backend_down = false;
+        for (path, mut entry) in self.entries()? {
+            if Utc::now() - entry.first_failed_at > self.max_age {
+                self.bury(&path, &entry, "expired")?;
+                report.dead += 1;
+                continue;
+            }
+            if backend_down {
+                report.pending += 1;
+                continue;
+            }
+
+            entry.attempts += 1;
+            entry.last_attempt_at = Utc::now();
+            match sink.publish(&entry.message).await {
+                Ok(_) => {
+                    std::fs::remove_file(&path)?;
+                    report.delivered += 1;
+                }
+                Err(e) => match e.downcast_ref::<ScrapeError>() {
+                    Some(error) if error.is_transient() => {
+                        entry.last_error = e.to_string();
+                        self.write(&path, &entry)?;
+                        report.pending += 1;
+                        backend_down = true;
+                    }
+                    _ => {
+                        entry.last_error = e.to_string();
+                        self.bury(&path, &entry, "rejected")?;
+                        report.dead += 1;
+                    }
+                },
+            }
+        }
+        info!("Outbox drained: {}", report);
+        Ok(report)
+    }
+
+    // Written aside and renamed, so a crash never leaves half an entry
+    fn write(&self, path: &Path, entry: &OutboxEntry) -> Result<(), Report> {
+        let temporary = path.with_extension("tmp");
+        std::fs::write(&temporary, serde_json::to_vec_pretty(entry)?)?;
+        std::fs::rename(&temporary, path)?;
+        Ok(())
+    }
+
+    fn bury(&self, path: &Path, entry: &OutboxEntry, reason: &str) -> Result<(), Report> {
+        #[derive(Serialize)]
+        struct DeadLetter<'a> {
+            reason: &'a str,
+            #[serde(flatten)]
+            entry: &'a OutboxEntry,
+        }
+
+        warn!("Giving up on {} after {} attempts: {}", entry.message.body().url, entry.attempts, reason);
+        let mut line = serde_json::to_vec(&DeadLetter { reason, entry })?;
+        line.push(b'\n');
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.dir.join("dead-letter.jsonl"))?
+            .write_all(&line)?;
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+}
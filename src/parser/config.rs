@@ -1,5 +0,0 @@
-#[derive(Debug, Clone)]
-pub struct Configuration {
-    pub server_address: String,
-    pub post_endpoint: String
-}
\ No newline at end of file
@@ -0,0 +1,154 @@
+use color_eyre::Report;
+use reqwest::Url;
+use scraper::{Html, Selector};
+use serde_json::Value;
+use crate::configuration::{DetailField, DetailSettings};
+use crate::parser::selector::{parse_selector, FieldSelector};
+use crate::types::ProductDetails;
+
+// Compiled `FeatureTableSettings`
+#[derive(Debug)]
+struct FeatureTable {
+    name: Selector,
+    value: Selector,
+    labels: Vec<(DetailField, String)>,
+}
+
+/// Reads `ProductDetails` out of product pages, see `DetailSettings`.
+#[derive(Debug)]
+pub struct DetailExtractor {
+    fields: Vec<(DetailField, FieldSelector)>,
+    features: Option<FeatureTable>,
+    json_ld: Selector,
+}
+
+fn slot(details: &mut ProductDetails, field: DetailField) -> &mut Option<String> {
+    match field {
+        DetailField::Ean => &mut details.ean,
+        DetailField::Sku => &mut details.sku,
+        DetailField::Publisher => &mut details.publisher,
+        DetailField::Players => &mut details.players,
+        DetailField::Playtime => &mut details.playtime,
+        DetailField::AgeRating => &mut details.age_rating,
+        DetailField::Image => &mut details.image_url,
+    }
+}
+
+// Fills `field` unless it already has a value, cleaning up what shops write
+fn fill(details: &mut ProductDetails, field: DetailField, value: &str, page_url: &str) {
+    let slot = slot(details, field);
+    if slot.is_some() {
+        return;
+    }
+
+    let value = value.split_whitespace().collect::<Vec<_>>().join(" ");
+    *slot = match field {
+        // Only lengths a GTIN can have, anything else is a typo or a reference
+        DetailField::Ean => {
+            let digits: String = value.chars().filter(char::is_ascii_digit).collect();
+            Some(digits).filter(|digits| [8, 12, 13, 14].contains(&digits.len()))
+        }
+        DetailField::Image => Url::parse(page_url).and_then(|base| base.join(&value)).ok().map(String::from),
+        _ => Some(value).filter(|value| !value.is_empty()),
+    };
+}
+
+// The schema.org Product of the page, wherever it is in the JSON-LD
+fn find_product(value: &Value) -> Option<&Value> {
+    match value {
+        Value::Array(items) => items.iter().find_map(find_product),
+        Value::Object(object) => {
+            let is_product = match object.get("@type") {
+                Some(Value::String(kind)) => kind == "Product",
+                Some(Value::Array(kinds)) => kinds.iter().any(|kind| kind == "Product"),
+                _ => false,
+            };
+            match is_product {
+                true => Some(value),
+                false => object.get("@graph").and_then(find_product),
+            }
+        }
+        _ => None,
+    }
+}
+
+// Text of a JSON-LD property, which can be a string, a number, an object with
+// a name or url, or a list of any of them
+fn json_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.clone()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::Array(items) => items.iter().find_map(json_text),
+        Value::Object(object) => object.get("name").or_else(|| object.get("url")).and_then(json_text),
+        _ => None,
+    }
+}
+
+impl DetailExtractor {
+
+    pub fn new(settings: &DetailSettings) -> Result<DetailExtractor, Report> {
+        let fields = settings.fields.iter()
+            .map(|(field, settings)| Ok((*field, FieldSelector::new(settings)?)))
+            .collect::<Result<_, Report>>()?;
+        let features = match &settings.features {
+            Some(table) => Some(FeatureTable {
+                name: parse_selector(&table.name)?,
+                value: parse_selector(&table.value)?,
+                labels: table.labels.iter().map(|(field, label)| (*field, label.to_lowercase())).collect(),
+            }),
+            None => None,
+        };
+
+        Ok(DetailExtractor {
+            fields,
+            features,
+            json_ld: parse_selector("script[type='application/ld+json']")?,
+        })
+    }
+
+    /// Whatever `body`, the product page at `page_url`, tells. Missing details
+    /// are just left empty.
+    pub fn extract(&self, body: &str, page_url: &str) -> ProductDetails {
+        let document = Html::parse_document(body);
+        let mut details = ProductDetails::default();
+
+        for (field, selector) in &self.fields {
+            if let Some(value) = selector.extract(document.root_element()) {
+                fill(&mut details, *field, &value, page_url);
+            }
+        }
+
+        if let Some(table) = &self.features {
+            let names = document.select(&table.name).map(|name| name.text().collect::<String>());
+            let values = document.select(&table.value).map(|value| value.text().collect::<String>());
+            for (name, value) in names.zip(values) {
+                let name = name.trim().trim_end_matches(':').trim().to_lowercase();
+                for (field, _) in table.labels.iter().filter(|(_, label)| *label == name) {
+                    fill(&mut details, *field, &value, page_url);
+                }
+            }
+        }
+
+        let products = document.select(&self.json_ld)
+            .filter_map(|script| serde_json::from_str::<Value>(&script.text().collect::<String>()).ok());
+        for json in products {
+            let product = match find_product(&json) {
+                Some(product) => product,
+                None => continue,
+            };
+            let properties = [
+                (DetailField::Ean, &["gtin13", "gtin", "gtin12", "gtin14", "gtin8", "ean"][..]),
+                (DetailField::Sku, &["sku", "mpn"][..]),
+                (DetailField::Publisher, &["brand", "manufacturer"][..]),
+                (DetailField::Image, &["image"][..]),
+            ];
+            for (field, keys) in properties {
+                if let Some(value) = keys.iter().find_map(|key| product.get(*key).and_then(json_text)) {
+                    fill(&mut details, field, &value, page_url);
+                }
+            }
+        }
+
+        details
+    }
+}
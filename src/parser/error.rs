@@ -0,0 +1,108 @@
+use std::collections::BTreeMap;
+use serde::Serialize;
+
+/// Field of a product that could not be extracted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Field {
+    Name,
+    Url,
+    OfferPrice,
+    /// Full name in the product page, for shops that truncate names
+    DetailName,
+}
+
+impl std::fmt::Display for Field {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Field::Name => "name",
+            Field::Url => "url",
+            Field::OfferPrice => "offer_price",
+            Field::DetailName => "detail_name",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Everything that can go wrong between a shop page and the backend
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ScrapeError {
+    #[error("missing {0}")]
+    MissingField(Field),
+    #[error("bad price `{0}`")]
+    BadPrice(String),
+    #[error("name is truncated and there is no way to recover it")]
+    TruncatedName,
+    #[error("shop answered {0}")]
+    HttpStatus(u16),
+    #[error("request failed: {0}")]
+    Http(String),
+    #[error("backend answered {0}")]
+    BackendRejected(u16),
+    #[error("no products found, the layout probably changed")]
+    LayoutChanged,
+}
+
+impl ScrapeError {
+
+    /// Short, stable identifier used to count failures of the same kind
+    pub fn kind(&self) -> String {
+        match self {
+            ScrapeError::MissingField(field) => format!("missing_field({})", field),
+            ScrapeError::BadPrice(_) => "bad_price".to_string(),
+            ScrapeError::TruncatedName => "truncated_name".to_string(),
+            ScrapeError::HttpStatus(code) => format!("http_status({})", code),
+            ScrapeError::Http(_) => "http".to_string(),
+            ScrapeError::BackendRejected(code) => format!("backend_rejected({})", code),
+            ScrapeError::LayoutChanged => "layout_changed".to_string(),
+        }
+    }
+
+    /// Whether the same request may work later: no answer at all, a timeout
+    /// or a server error
+    pub fn is_transient(&self) -> bool {
+        match self {
+            ScrapeError::Http(_) => true,
+            ScrapeError::HttpStatus(code) | ScrapeError::BackendRejected(code) => *code == 408 || *code >= 500,
+            _ => false,
+        }
+    }
+}
+
+impl From<reqwest::Error> for ScrapeError {
+    fn from(e: reqwest::Error) -> Self {
+        match e.status() {
+            Some(status) => ScrapeError::HttpStatus(status.as_u16()),
+            None => ScrapeError::Http(e.to_string()),
+        }
+    }
+}
+
+/// What happened while scraping a shop
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ScrapeStats {
+    pub pages_fetched: usize,
+    pub entries_seen: usize,
+    pub offers: usize,
+    /// Entries dropped on purpose by a name rule
+    pub rejected: usize,
+    /// Failures by `ScrapeError::kind`
+    pub failures: BTreeMap<String, usize>,
+}
+
+impl ScrapeStats {
+
+    pub fn record_failure(&mut self, error: &ScrapeError) {
+        *self.failures.entry(error.kind()).or_default() += 1;
+    }
+
+    pub fn merge(&mut self, other: &ScrapeStats) {
+        self.pages_fetched += other.pages_fetched;
+        self.entries_seen += other.entries_seen;
+        self.offers += other.offers;
+        self.rejected += other.rejected;
+        for (kind, count) in &other.failures {
+            *self.failures.entry(kind.clone()).or_default() += count;
+        }
+    }
+}
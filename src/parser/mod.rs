@@ -1,11 +1,13 @@
-mod dracotienda;
-mod jugamosotra;
-mod dungeonmarvels;
-mod config;
+mod selector;
+mod details;
+mod price;
+mod error;
+mod registry;
+#[allow(clippy::module_inception)]
 mod parser;
 
-pub use dracotienda::*;
-pub use jugamosotra::JugamosotraParser;
-pub use dungeonmarvels::DungeonMarvelsParser;
-pub use config::Configuration;
+pub use selector::SelectorShopParser;
 pub use parser::ShopParser;
+pub use price::{normalize_amount, parse_price, PriceError};
+pub use error::{Field, ScrapeError, ScrapeStats};
+pub use registry::{ParserConstructor, ParserContext, RegisteredShop, ShopRegistry};
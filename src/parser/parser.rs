@@ -1,5 +1,15 @@
+use async_trait::async_trait;
 use color_eyre::Report;
+use tokio::sync::mpsc::Sender;
+use crate::types::ScrapeEvent;
+use crate::parser::ScrapeStats;
+use crate::http::Fetch;
 
-pub trait ShopParser {
-    fn process(&self, client: &reqwest::blocking::Client, url: &str, limit: i32) -> Result<(), Report>;
+#[async_trait]
+pub trait ShopParser: Send + Sync {
+    /// Scrapes up to `limit` entries (aprox) starting at `url` and streams every
+    /// resulting offer, or the reason it was rejected, through `events`.
+    /// Publishing them is up to the receiver, the returned stats account for
+    /// what could not even be turned into an offer.
+    async fn process(&self, client: &dyn Fetch, url: &str, limit: i32, events: &Sender<ScrapeEvent>) -> Result<ScrapeStats, Report>;
 }
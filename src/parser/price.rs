@@ -0,0 +1,110 @@
+use crate::types::{Currency, Money};
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum PriceError {
+    #[error("empty price")]
+    Empty,
+    #[error("no amount in price `{0}`")]
+    NoAmount(String),
+    #[error("unable to parse amount in price `{0}`")]
+    BadAmount(String),
+}
+
+/* Parses the price tags found in (mostly Spanish) shops: "1.234,56 €",
+ * "Desde 12,00 €", "12,00\u{a0}€", "€12.50"... Only the first amount of the
+ * text is taken into account and, unless a currency symbol or code is found
+ * next to it, `default_currency` is assumed.
+ */
+pub fn parse_price(input: &str, default_currency: Currency) -> Result<Money, PriceError> {
+    // Non breaking spaces are just spaces
+    let text = input.replace(['\u{a0}', '\u{202f}'], " ");
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(PriceError::Empty);
+    }
+
+    // Anything before the first digit is a prefix such as "Desde" or a symbol
+    let start = text.find(|c: char| c.is_ascii_digit()).ok_or_else(|| PriceError::NoAmount(input.to_string()))?;
+    let (prefix, rest) = text.split_at(start);
+
+    // The amount goes on while there are digits, separators or grouping spaces
+    let mut end = 0;
+    for (i, c) in rest.char_indices() {
+        if c.is_ascii_digit() {
+            end = i + 1;
+        } else if !matches!(c, '.' | ',' | ' ') {
+            break;
+        }
+    }
+    let (raw_amount, suffix) = rest.split_at(end);
+    // A space only counts as a thousands separator ("1 234,56") when it comes
+    // before any other separator and is followed by 3 digits. Otherwise it is
+    // the end of the amount ("12,00 € 10,00 €").
+    let mut groups = raw_amount.split(' ');
+    let mut amount = groups.next().unwrap_or_default().to_string();
+    for group in groups {
+        let leading_digits = group.chars().take_while(|c| c.is_ascii_digit()).count();
+        if leading_digits != 3 || amount.contains(['.', ',']) {
+            break;
+        }
+        amount.push_str(group);
+    }
+
+    let amount = normalize_amount(&amount).ok_or_else(|| PriceError::BadAmount(input.to_string()))?;
+    let amount = Decimal::from_str(&amount).map_err(|_| PriceError::BadAmount(input.to_string()))?;
+
+    let currency = find_currency(suffix)
+        .or_else(|| find_currency(prefix))
+        .unwrap_or(default_currency);
+
+    Ok(Money::new(amount, currency))
+}
+
+/// Turns "1.234,56", "1,234.56", "12,5"... into "1234.56"-like strings. A
+/// single separator followed by 3 digits is taken for thousands.
+pub fn normalize_amount(amount: &str) -> Option<String> {
+    let last_dot = amount.rfind('.');
+    let last_comma = amount.rfind(',');
+
+    let decimal_separator = match (last_dot, last_comma) {
+        (None, None) => None,
+        // Both are there, the last one separates the decimals
+        (Some(dot), Some(comma)) => Some(if dot > comma { '.' } else { ',' }),
+        // Only one kind: decimals when used once and not followed by 3 digits
+        (Some(position), None) | (None, Some(position)) => {
+            let separator = amount[position..].chars().next()?;
+            let digits_after = amount.len() - position - 1;
+            if amount.matches(separator).count() == 1 && digits_after != 3 {
+                Some(separator)
+            } else {
+                None
+            }
+        }
+    };
+
+    let mut result = String::new();
+    for c in amount.chars() {
+        if c.is_ascii_digit() {
+            result.push(c);
+        } else if Some(c) == decimal_separator {
+            result.push('.');
+        }
+    }
+
+    if result.is_empty() || result.starts_with('.') {
+        return None;
+    }
+    Some(result)
+}
+
+fn find_currency(text: &str) -> Option<Currency> {
+    text.split(|c: char| c.is_whitespace() || c.is_ascii_digit())
+        .flat_map(|word| {
+            // Symbols can be glued to anything, codes are whole words
+            let symbol = word.chars().find(|c| matches!(c, '€' | '$' | '£')).map(String::from);
+            symbol.into_iter().chain(std::iter::once(word.to_string()))
+        })
+        .find_map(|word| Currency::from_symbol(&word))
+}
@@ -0,0 +1,127 @@
+use std::sync::Arc;
+use color_eyre::{eyre::eyre, Report};
+use tokio::sync::Semaphore;
+use crate::configuration::{AvailabilitySettings, NameRuleSettings, ShopSettings};
+use crate::types::ProductType;
+use crate::parser::{SelectorShopParser, ShopParser};
+
+/// Everything shared by all the parsers of a run
+#[derive(Debug, Clone)]
+pub struct ParserContext {
+    pub name_rules: Vec<NameRuleSettings>,
+    pub availability: Vec<AvailabilitySettings>,
+    /// Entries of any other type are rejected instead of becoming offers
+    pub publish_types: Vec<ProductType>,
+    pub global_limit: Arc<Semaphore>,
+    /// Stamped on every offer, tells apart the observations of each crawl
+    pub run_id: String,
+    /// Visit every product page for `ProductDetails`, see `DetailSettings`
+    pub details: bool,
+}
+
+pub type ParserConstructor = fn(ShopSettings, &ParserContext) -> Result<Box<dyn ShopParser>, Report>;
+
+// Parser kinds a shop can use through its `kind` setting
+const KINDS: &[(&str, ParserConstructor)] = &[
+    ("selector", build_selector_parser),
+];
+
+fn build_selector_parser(shop: ShopSettings, context: &ParserContext) -> Result<Box<dyn ShopParser>, Report> {
+    Ok(Box::new(SelectorShopParser::new(shop, context)?))
+}
+
+/// A shop that can be scraped: its settings, start URLs included, and how to
+/// build its parser.
+#[derive(Clone)]
+pub struct RegisteredShop {
+    pub settings: ShopSettings,
+    constructor: ParserConstructor,
+}
+
+impl RegisteredShop {
+    pub fn build(&self, context: &ParserContext) -> Result<Box<dyn ShopParser>, Report> {
+        (self.constructor)(self.settings.clone(), context)
+    }
+}
+
+/// Every shop known to this run, in configuration order.
+pub struct ShopRegistry {
+    shops: Vec<RegisteredShop>,
+}
+
+impl ShopRegistry {
+
+    pub fn new(shops: &[ShopSettings]) -> Result<ShopRegistry, Report> {
+        let mut registered: Vec<RegisteredShop> = vec![];
+        for shop in shops {
+            if registered.iter().any(|r| r.settings.id == shop.id) {
+                return Err(eyre!("Shop `{}` is defined twice", shop.id));
+            }
+            // Both would leave the shop without a single page to scrape
+            if shop.page_size < 1 {
+                return Err(eyre!("Shop `{}` needs a page_size of at least 1, not {}", shop.id, shop.page_size));
+            }
+            if shop.concurrency == 0 {
+                return Err(eyre!("Shop `{}` needs a concurrency of at least 1", shop.id));
+            }
+            let constructor = KINDS.iter()
+                .find(|(kind, _)| *kind == shop.kind)
+                .map(|(_, constructor)| *constructor)
+                .ok_or_else(|| eyre!("Unknown parser kind `{}` for shop `{}`", shop.kind, shop.id))?;
+            registered.push(RegisteredShop { settings: shop.clone(), constructor });
+        }
+        Ok(ShopRegistry { shops: registered })
+    }
+
+    pub fn get(&self, id: &str) -> Option<&RegisteredShop> {
+        self.shops.iter().find(|shop| shop.settings.id == id)
+    }
+
+    /* Resolves a comma separated selection such as "dracotienda,jugamosotra".
+     * `all` stands for every shop and a leading `-` excludes one, so
+     * "all,-jugamosotra" is everything but jugamosotra. A selection made only
+     * of exclusions starts from `all` too.
+     */
+    pub fn select(&self, selection: &str) -> Result<Vec<&RegisteredShop>, Report> {
+        let items: Vec<&str> = selection.split(',').map(str::trim).filter(|item| !item.is_empty()).collect();
+        let check = |id: &str| match self.get(id) {
+            Some(_) => Ok(()),
+            None => Err(eyre!("Unknown shop `{}`, use --list-shops to see the available ones", id)),
+        };
+
+        let mut included: Vec<&str> = vec![];
+        let mut excluded: Vec<&str> = vec![];
+        for item in &items {
+            match item.strip_prefix('-') {
+                Some(id) => {
+                    check(id)?;
+                    excluded.push(id);
+                }
+                None if *item == "all" => included.extend(self.shops.iter().map(|shop| shop.settings.id.as_str())),
+                None => {
+                    check(item)?;
+                    included.push(item);
+                }
+            }
+        }
+        if included.is_empty() && !excluded.is_empty() {
+            included.extend(self.shops.iter().map(|shop| shop.settings.id.as_str()));
+        }
+
+        Ok(self.shops.iter()
+            .filter(|shop| included.contains(&shop.settings.id.as_str()))
+            .filter(|shop| !excluded.contains(&shop.settings.id.as_str()))
+            .collect())
+    }
+
+    /// One line per shop, for `--list-shops`
+    pub fn describe(&self) -> String {
+        self.shops.iter()
+            .map(|shop| {
+                let settings = &shop.settings;
+                format!("{:<16} {:<16} {:<10} {}", settings.id, settings.name, settings.kind, settings.start_urls.join(" "))
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
@@ -0,0 +1,385 @@
+use crate::types::{Money, Offer, Page, PageEntry, ProductDetails, ProductType, Rejection, ScrapeEvent, OFFER_SCHEMA_VERSION};
+use crate::configuration::{FieldSettings, ShopSettings};
+use chrono::DateTime;
+use chrono::Utc;
+use color_eyre::{eyre::eyre, Report};
+use std::sync::Arc;
+use async_trait::async_trait;
+use futures::future::join_all;
+use tokio::sync::{mpsc::Sender, Semaphore, SemaphorePermit};
+use tracing::{info, warn, error};
+use scraper::{ElementRef, Html, Selector};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use crate::parser::ShopParser;
+use tracing::instrument;
+use crate::telemetry::{PropagationContext, SpannedMessage};
+use crate::rules::{AvailabilityMap, NameRules, NameVerdict};
+use crate::parser::price::parse_price;
+use crate::parser::{Field, ParserContext, ScrapeError, ScrapeStats};
+use crate::http::Fetch;
+use crate::parser::details::DetailExtractor;
+
+/* Generic parser for PrestaShop-like stores. Listings are a sequence of pages,
+ * each one with several product containers and a link to the next page, so
+ * the only thing that changes from shop to shop are the selectors, which come
+ * from the `shops` section of the configuration.
+ */
+#[derive(Debug)]
+pub struct SelectorShopParser {
+    pub shop: ShopSettings,
+    container: Selector,
+    name: FieldSelector,
+    url: FieldSelector,
+    offer_price: FieldSelector,
+    normal_price: Option<FieldSelector>,
+    availability: Option<FieldSelector>,
+    next_page: FieldSelector,
+    detail_name: Option<Selector>,
+    /// Only in runs with `--details`
+    details: Option<DetailExtractor>,
+    rules: NameRules,
+    availability_map: AvailabilityMap,
+    publish_types: Vec<ProductType>,
+    limit: Semaphore,
+    global_limit: Arc<Semaphore>,
+    run_id: String,
+}
+
+#[derive(Debug)]
+pub(super) struct FieldSelector {
+    selector: Selector,
+    settings: FieldSettings,
+}
+
+pub(super) fn parse_selector(selector: &str) -> Result<Selector, Report> {
+    Selector::parse(selector).map_err(|e| eyre!("Bad selector `{}`: {:?}", selector, e))
+}
+
+impl FieldSelector {
+
+    pub(super) fn new(settings: &FieldSettings) -> Result<FieldSelector, Report> {
+        Ok(FieldSelector {
+            selector: parse_selector(&settings.selector)?,
+            settings: settings.clone(),
+        })
+    }
+
+    pub(super) fn extract(&self, element: ElementRef) -> Option<String> {
+        let found = match element.select(&self.selector).next() {
+            Some(found) => found,
+            None => return self.settings.default.clone(),
+        };
+
+        if let Some(value) = &self.settings.value {
+            return Some(value.clone());
+        }
+
+        let mut value = match &self.settings.attr {
+            Some(attr) => found.value().attr(attr)?.to_string(),
+            None if self.settings.first_text => found.text().next()?.to_string(),
+            None => found.text().collect::<String>(),
+        };
+
+        if self.settings.alphanumeric {
+            value.retain(|c| c.is_alphanumeric() || c.is_whitespace());
+        }
+
+        Some(value.trim().to_string())
+    }
+}
+
+// Text of the first element matching `selector` in a whole document
+fn select_text(body: &str, selector: &Selector) -> Option<String> {
+    let document = Html::parse_document(body);
+    document.select(selector).next().map(|element| element.text().collect::<String>())
+}
+
+
+impl SelectorShopParser {
+
+    /// Builds the parser for `shop`, whose own name rules are merged with the
+    /// global ones of `context`. Every request it makes holds a permit of both
+    /// its own semaphore and `context.global_limit`.
+    pub fn new(shop: ShopSettings, context: &ParserContext) -> Result<SelectorShopParser, Report> {
+        let selectors = &shop.selectors;
+        let detail_name = match shop.truncated_name.as_ref().and_then(|t| t.detail_selector.as_ref()) {
+            Some(selector) => Some(parse_selector(selector)?),
+            None => None,
+        };
+
+        Ok(SelectorShopParser {
+            container: parse_selector(&selectors.container)?,
+            name: FieldSelector::new(&selectors.name)?,
+            url: FieldSelector::new(&selectors.url)?,
+            offer_price: FieldSelector::new(&selectors.offer_price)?,
+            normal_price: selectors.normal_price.as_ref().map(FieldSelector::new).transpose()?,
+            availability: selectors.availability.as_ref().map(FieldSelector::new).transpose()?,
+            next_page: FieldSelector::new(&selectors.next_page)?,
+            detail_name,
+            details: match context.details {
+                true => Some(DetailExtractor::new(&shop.details)?),
+                false => None,
+            },
+            rules: NameRules::compile(&context.name_rules, &shop.name_rules)?,
+            availability_map: AvailabilityMap::compile(&context.availability, &shop.availability),
+            publish_types: context.publish_types.clone(),
+            limit: Semaphore::new(shop.concurrency),
+            global_limit: context.global_limit.clone(),
+            run_id: context.run_id.clone(),
+            shop,
+        })
+    }
+
+    /// Splits a listing page into its raw entries and the link to the next page.
+    pub fn process_page(&self, body: &str) -> Result<Page, ScrapeError> {
+        let fragment = Html::parse_document(body);
+
+        let entries: Vec<PageEntry> = fragment.select(&self.container)
+            .map(|entry| PageEntry {
+                name: self.name.extract(entry),
+                url: self.url.extract(entry),
+                offer_price: self.offer_price.extract(entry),
+                normal_price: self.normal_price.as_ref().and_then(|field| field.extract(entry)),
+                availability: self.availability.as_ref().and_then(|field| field.extract(entry)),
+            })
+            .filter(|entry| entry.name.is_some() || !self.shop.selectors.skip_unnamed)
+            .collect();
+
+        // A listing without products is not a listing anymore
+        if entries.is_empty() {
+            return Err(ScrapeError::LayoutChanged);
+        }
+
+        Ok(Page {
+            next_url: self.next_page.extract(fragment.root_element()),
+            entries,
+        })
+    }
+
+    /* Some shops shorten the game name and add `...` to it, so we need to enter
+     * into the offer URL and check it by hand. Runs with `--details` visit the
+     * product page of every offer too. Either way the page is fetched once and
+     * the rest of data is parsed from the listing.
+     */
+    async fn fetch_product_page(&self, client: &dyn Fetch, url: &str) -> Result<String, ScrapeError> {
+
+        // Create a delay, holding the shop permit so it works as a rate limit.
+        // Both settings delay the same request, the longer one wins.
+        let _shop_permit = acquire(&self.limit).await;
+        let truncated_delay = self.shop.truncated_name.as_ref().map(|t| t.detail_delay_secs).unwrap_or_default();
+        let details_delay = self.details.as_ref().map(|_| self.shop.details.delay_secs).unwrap_or_default();
+        tokio::time::sleep(std::time::Duration::from_secs(truncated_delay.max(details_delay))).await;
+        let _global_permit = acquire(&self.global_limit).await;
+
+        // TODO: Put this in a loop, sometimes we get err 500
+        let response = client.fetch(url).await?;
+        if response.status != 200 {
+            return Err(ScrapeError::HttpStatus(response.status));
+        }
+        Ok(response.body)
+    }
+
+    /// Gets the body of a listing page, retrying a few times before giving up.
+    async fn fetch_listing(&self, client: &dyn Fetch, url: &str) -> Result<String, ScrapeError> {
+        let mut result = Err(ScrapeError::Http(format!("Unable to get {}", url)));
+
+        for attempt in 0..3 {
+            if attempt > 0 {
+                warn!("Retrying {} after {}", url, result.as_ref().err().map(|e| e.to_string()).unwrap_or_default());
+                tokio::time::sleep(std::time::Duration::from_secs(self.shop.retry_delay_secs)).await;
+            }
+
+            let _shop_permit = acquire(&self.limit).await;
+            let _global_permit = acquire(&self.global_limit).await;
+            result = match client.fetch(url).await {
+                Ok(page) if page.status == 200 => Ok(page.body),
+                Ok(page) => Err(ScrapeError::HttpStatus(page.status)),
+                Err(e) => Err(e),
+            };
+
+            if result.is_ok() {
+                break;
+            }
+        }
+        result
+    }
+
+    #[instrument(level = "info", name = "Processing entry", skip(self, client, entry), fields(error_detail="OK", shop=%self.shop.name), err(Display))]
+    pub async fn process_entry(&self, client: &dyn Fetch, entry: PageEntry, url: &str, batch_name: &str) -> Result<ScrapeEvent, ScrapeError> {
+        let result = self.build_event(client, entry, batch_name).await;
+        if let Err(e) = &result {
+            tracing::Span::current().record("error_detail", e.kind().as_str());
+        }
+        result
+    }
+
+    fn reject(&self, url: String, raw_name: String, rule: String) -> ScrapeEvent {
+        info!("{} skipped by rule {}", raw_name, rule);
+        tracing::Span::current().record("error_detail", "rejected");
+        ScrapeEvent::Rejected(Rejection {
+            url,
+            raw_name,
+            shop_name: self.shop.name.clone(),
+            rule,
+        })
+    }
+
+    async fn build_event(&self, client: &dyn Fetch, entry: PageEntry, batch_name: &str) -> Result<ScrapeEvent, ScrapeError> {
+
+        // Get name and url
+        let mut raw_name = entry.name.ok_or(ScrapeError::MissingField(Field::Name))?;
+        let link = entry.url.ok_or(ScrapeError::MissingField(Field::Url))?;
+
+        // Recover shortened names from the product page
+        let truncated = self.shop.truncated_name.as_ref().is_some_and(|t| raw_name.contains(t.marker.as_str()));
+        let mut product_page = None;
+        if truncated {
+            let selector = self.detail_name.as_ref().ok_or(ScrapeError::TruncatedName)?;
+            let body = self.fetch_product_page(client, &link).await?;
+            raw_name = select_text(&body, selector).ok_or(ScrapeError::MissingField(Field::DetailName))?;
+            info!("Processed name from {} into {}", link, raw_name);
+            product_page = Some(body);
+        }
+        info!("Processing {}", raw_name);
+
+        // Process name, remove weird offers
+        let (name, name_rules, tags) = match self.rules.apply(&raw_name) {
+            NameVerdict::Accepted { name, fired, tags } => (name, fired, tags),
+            NameVerdict::Rejected { rule } => return Ok(self.reject(link, raw_name, rule)),
+        };
+        info!("Game processed to {} {:?}", name, name_rules);
+
+        // Only the product types wanted go on
+        let product_type = tags.product_type.unwrap_or_default();
+        if !self.publish_types.contains(&product_type) {
+            let rule = tags.product_type_rule.unwrap_or_else(|| String::from("product_type"));
+            return Ok(self.reject(link, raw_name, rule));
+        }
+
+        // Get offer price. If there is no normal price, then is not a discount but a normal offer.
+        let offer_price = entry.offer_price.ok_or(ScrapeError::MissingField(Field::OfferPrice))?;
+        let offer_price = parse_price(&offer_price, self.shop.currency).map_err(|_| ScrapeError::BadPrice(offer_price))?;
+        let normal_price = match entry.normal_price {
+            Some(price) => parse_price(&price, self.shop.currency).map_err(|_| ScrapeError::BadPrice(price))?,
+            None => offer_price,
+        };
+
+        // Get availability
+        let raw_availability = entry.availability.unwrap_or_default();
+        let availability = self.availability_map.classify(&raw_availability);
+        info!("Availability: {} ({})", availability, raw_availability);
+
+        // Discount, both ways
+        let savings = Money::new(normal_price.amount - offer_price.amount, offer_price.currency);
+        let discount_percent = savings.percent_of(&normal_price);
+
+        // Only entries that become offers are worth a visit for details, which
+        // are a bonus: the offer is still good without them
+        if product_page.is_none() && self.details.is_some() {
+            product_page = match self.fetch_product_page(client, &link).await {
+                Ok(body) => Some(body),
+                Err(e) => {
+                    warn!("No details for {}: {}", link, e);
+                    None
+                }
+            };
+        }
+        let details = match (&self.details, &product_page) {
+            (Some(extractor), Some(body)) => extractor.extract(body, &link),
+            _ => ProductDetails::default(),
+        };
+
+        // Create the object offer
+        let current_offer = Offer {
+            schema_version: OFFER_SCHEMA_VERSION,
+            name,
+            url: link,
+            offer_price,
+            normal_price,
+            currency: offer_price.currency,
+            discount_percent,
+            savings,
+            availability,
+            raw_availability,
+            shop_name: self.shop.name.clone(),
+            raw_name,
+            name_rules,
+            language: tags.language,
+            condition: tags.condition.unwrap_or_default(),
+            product_type,
+            details,
+            batch: batch_name.to_string(),
+            run_id: self.run_id.clone(),
+            scraped_at: Utc::now(),
+        };
+        info!("{:?}", current_offer);
+
+        let propagation_context = PropagationContext::inject(&tracing::Span::current().context());
+        Ok(ScrapeEvent::Offer(SpannedMessage::new(propagation_context, current_offer)))
+    }
+}
+
+// Semaphores are never closed, so acquiring can not fail
+async fn acquire(semaphore: &Semaphore) -> SemaphorePermit<'_> {
+    semaphore.acquire().await.expect("Semaphore closed")
+}
+
+#[async_trait]
+impl ShopParser for SelectorShopParser {
+
+    async fn process(&self, client: &dyn Fetch, url: &str, limit: i32, events: &Sender<ScrapeEvent>) -> Result<ScrapeStats, Report> {
+        // Epoch information
+        let now: DateTime<Utc> = Utc::now();
+        let formatted_now = now.format("%Y-%m-%d_%H").to_string();
+
+        let mut stats = ScrapeStats::default();
+        let limit = limit / self.shop.page_size + 1;
+        let mut body = Some(self.fetch_listing(client, url).await);
+
+        for page_number in 0..limit {
+            let page = match body.take() {
+                Some(Ok(current)) => {
+                    stats.pages_fetched += 1;
+                    self.process_page(&current)
+                }
+                Some(Err(e)) => Err(e),
+                None => break,
+            };
+            let page = match page {
+                Ok(page) => page,
+                Err(e) => {
+                    error!("Stopping {}: {}", self.shop.name, e);
+                    stats.record_failure(&e);
+                    break;
+                }
+            };
+
+            // Process offers in current page while the next one is downloaded
+            let entries = join_all(page.entries.into_iter().map(|entry| self.process_entry(client, entry, url, &formatted_now)));
+            let next_page = async {
+                match page.next_url {
+                    Some(next_url) if page_number + 1 < limit => Some(self.fetch_listing(client, &next_url).await),
+                    _ => None,
+                }
+            };
+            let (results, next_body) = tokio::join!(entries, next_page);
+
+            for result in results {
+                stats.entries_seen += 1;
+                match result {
+                    Ok(event) => {
+                        match &event {
+                            ScrapeEvent::Offer(_) => stats.offers += 1,
+                            ScrapeEvent::Rejected(_) => stats.rejected += 1,
+                        }
+                        events.send(event).await?;
+                    }
+                    Err(e) => stats.record_failure(&e),
+                }
+            }
+            body = next_body;
+        }
+
+        Ok(stats)
+    }
+}
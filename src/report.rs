@@ -0,0 +1,161 @@
+use std::collections::BTreeMap;
+use color_eyre::Report;
+use serde::Serialize;
+use crate::configuration::ReportSettings;
+use crate::parser::{ScrapeError, ScrapeStats};
+use crate::sink::Delivery;
+
+/// What happened to a shop along the whole run, from its pages to the backend
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ShopReport {
+    pub pages_fetched: usize,
+    pub entries_seen: usize,
+    pub skipped_by_rules: usize,
+    pub offers_posted: usize,
+    /// Backend answered 515, unable to match the offer with any game
+    pub unmatched: usize,
+    /// Backend answered 408
+    pub timeouts: usize,
+    /// Every other failure, scraping or publishing
+    pub other_failures: usize,
+    /// All the failures by `ScrapeError::kind`, timeouts included
+    pub failures: BTreeMap<String, usize>,
+}
+
+impl ShopReport {
+
+    pub fn failed(&self) -> usize {
+        self.timeouts + self.other_failures
+    }
+
+    /// Failures per entry seen. A shop that could not even get one entry
+    /// counts as fully failed.
+    pub fn failure_rate(&self) -> f64 {
+        if self.entries_seen == 0 {
+            return if self.failed() > 0 { 1.0 } else { 0.0 };
+        }
+        self.failed() as f64 / self.entries_seen as f64
+    }
+
+    fn record_failure(&mut self, kind: String) {
+        if kind == ScrapeError::BackendRejected(408).kind() {
+            self.timeouts += 1;
+        } else {
+            self.other_failures += 1;
+        }
+        *self.failures.entry(kind).or_default() += 1;
+    }
+}
+
+/// How to print the `RunSummary`, see `--report`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Table,
+    Json,
+}
+
+impl std::str::FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "table" => Ok(ReportFormat::Table),
+            "json" => Ok(ReportFormat::Json),
+            _ => Err(format!("Unknown report format `{}`, use `table` or `json`", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RunSummary {
+    pub shops: BTreeMap<String, ShopReport>,
+}
+
+impl RunSummary {
+
+    pub fn shop(&mut self, shop: &str) -> &mut ShopReport {
+        self.shops.entry(shop.to_string()).or_default()
+    }
+
+    pub fn record_scrape(&mut self, shop: &str, stats: &ScrapeStats) {
+        let report = self.shop(shop);
+        report.pages_fetched += stats.pages_fetched;
+        report.entries_seen += stats.entries_seen;
+        report.skipped_by_rules += stats.rejected;
+        for (kind, count) in &stats.failures {
+            for _ in 0..*count {
+                report.record_failure(kind.clone());
+            }
+        }
+    }
+
+    pub fn record_delivery(&mut self, shop: &str, delivery: &Result<Delivery, Report>) {
+        let report = self.shop(shop);
+        match delivery {
+            Ok(Delivery::Accepted) => report.offers_posted += 1,
+            Ok(Delivery::Unmatched) => report.unmatched += 1,
+            Err(e) => {
+                let kind = e.downcast_ref::<ScrapeError>()
+                    .map(|e| e.kind())
+                    .unwrap_or_else(|| String::from("publish"));
+                report.record_failure(kind);
+            }
+        }
+    }
+
+    /// Every threshold a shop went over, empty when the run went fine
+    pub fn violations(&self, thresholds: &ReportSettings) -> Vec<String> {
+        let mut violations = vec![];
+        for (shop, report) in &self.shops {
+            if report.failure_rate() > thresholds.max_failure_rate {
+                violations.push(format!("{}: {:.0}% of the entries failed", shop, report.failure_rate() * 100.0));
+            }
+            if report.offers_posted < thresholds.min_offers_posted {
+                violations.push(format!("{}: only {} offers posted", shop, report.offers_posted));
+            }
+            if let Some(max_timeouts) = thresholds.max_timeouts {
+                if report.timeouts > max_timeouts {
+                    violations.push(format!("{}: {} backend timeouts", shop, report.timeouts));
+                }
+            }
+        }
+        violations
+    }
+
+    pub fn to_table(&self) -> String {
+        let headers = ["shop", "pages", "entries", "skipped", "posted", "unmatched", "408", "failed"];
+        let rows: Vec<[String; 8]> = self.shops.iter()
+            .map(|(shop, r)| [
+                shop.clone(),
+                r.pages_fetched.to_string(),
+                r.entries_seen.to_string(),
+                r.skipped_by_rules.to_string(),
+                r.offers_posted.to_string(),
+                r.unmatched.to_string(),
+                r.timeouts.to_string(),
+                r.other_failures.to_string(),
+            ])
+            .collect();
+
+        let widths: Vec<usize> = (0..headers.len())
+            .map(|i| rows.iter().map(|row| row[i].len()).chain(std::iter::once(headers[i].len())).max().unwrap_or(0))
+            .collect();
+        let line = |cells: Vec<&str>| cells.iter().zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string();
+
+        let mut table = vec![line(headers.to_vec())];
+        for row in &rows {
+            table.push(line(row.iter().map(String::as_str).collect()));
+        }
+        for (shop, report) in &self.shops {
+            for (kind, count) in &report.failures {
+                table.push(format!("{}: {} x {}", shop, count, kind));
+            }
+        }
+        table.join("\n")
+    }
+}
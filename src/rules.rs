@@ -0,0 +1,149 @@
+use color_eyre::{eyre::eyre, Report};
+use regex::Regex;
+use crate::configuration::{AvailabilitySettings, NameRuleSettings, RuleAction};
+use crate::types::{Availability, Condition, ProductType};
+
+/// Ordered list of rules every scraped name goes through before becoming an
+/// offer. Regexes are compiled once, when the parser is built.
+#[derive(Debug, Clone)]
+pub struct NameRules {
+    rules: Vec<NameRule>,
+}
+
+#[derive(Debug, Clone)]
+struct NameRule {
+    id: String,
+    action: RuleAction,
+    regex: Regex,
+    replacement: String,
+    language: Option<String>,
+    condition: Option<Condition>,
+    product_type: Option<ProductType>,
+}
+
+/// What the rules found out about an entry besides its name. Each value comes
+/// from the first rule that fired with one.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NameTags {
+    pub language: Option<String>,
+    pub condition: Option<Condition>,
+    pub product_type: Option<ProductType>,
+    /// Id of the rule that set `product_type`
+    pub product_type_rule: Option<String>,
+}
+
+/// Outcome of running a name through the rules.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NameVerdict {
+    /// The name survived, `fired` lists the id of every rule that changed or
+    /// tagged it
+    Accepted { name: String, fired: Vec<String>, tags: NameTags },
+    /// A reject rule matched, the entry must not be published
+    Rejected { rule: String },
+}
+
+impl NameRules {
+
+    /// Merges the global rules with the ones of a shop. A shop rule with the
+    /// same id as a global one replaces it in place (`enabled: false` just
+    /// disables it), any other shop rule runs before the global ones.
+    pub fn compile(global: &[NameRuleSettings], shop: &[NameRuleSettings]) -> Result<NameRules, Report> {
+        let overrides = |rule: &NameRuleSettings| global.iter().any(|g| g.id == rule.id);
+
+        let merged = shop.iter()
+            .filter(|rule| !overrides(rule))
+            .chain(global.iter().map(|g| shop.iter().find(|s| s.id == g.id).unwrap_or(g)))
+            .filter(|rule| rule.enabled);
+
+        let mut rules = vec![];
+        for settings in merged {
+            let regex = Regex::new(&settings.pattern)
+                .map_err(|e| eyre!("Bad pattern for name rule `{}`: {}", settings.id, e))?;
+            rules.push(NameRule {
+                id: settings.id.clone(),
+                action: settings.action,
+                regex,
+                replacement: settings.replacement.clone(),
+                language: settings.language.clone(),
+                condition: settings.condition,
+                product_type: settings.product_type,
+            });
+        }
+
+        Ok(NameRules { rules })
+    }
+
+    pub fn apply(&self, name: &str) -> NameVerdict {
+        let mut result = name.to_string();
+        let mut fired = vec![];
+        let mut tags = NameTags::default();
+
+        for rule in &self.rules {
+            if !rule.regex.is_match(&result) {
+                continue;
+            }
+
+            match rule.action {
+                RuleAction::Reject => return NameVerdict::Rejected { rule: rule.id.clone() },
+                RuleAction::Strip => result = rule.regex.replace_all(&result, "").to_string(),
+                RuleAction::Replace => result = rule.regex.replace_all(&result, rule.replacement.as_str()).to_string(),
+                RuleAction::Tag => (),
+            }
+            fired.push(rule.id.clone());
+            tags.language = tags.language.or_else(|| rule.language.clone());
+            tags.condition = tags.condition.or(rule.condition);
+            if tags.product_type.is_none() && rule.product_type.is_some() {
+                tags.product_type = rule.product_type;
+                tags.product_type_rule = Some(rule.id.clone());
+            }
+        }
+
+        // Whatever got removed usually leaves some spaces behind
+        let name = result.split_whitespace().collect::<Vec<_>>().join(" ");
+
+        NameVerdict::Accepted { name, fired, tags }
+    }
+}
+
+// Neither case nor spacing make another text
+fn normalize(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+/* Turns the availability text of a shop into an `Availability`. The shop
+ * table goes first, then the global one. A text listed as is wins; otherwise
+ * the longest listed text found inside it does, so "No disponible" can be
+ * told apart from "Disponible" just by listing both. Case and spacing are
+ * ignored.
+ */
+#[derive(Debug, Clone)]
+pub struct AvailabilityMap {
+    entries: Vec<(String, Availability)>,
+}
+
+impl AvailabilityMap {
+
+    pub fn compile(global: &[AvailabilitySettings], shop: &[AvailabilitySettings]) -> AvailabilityMap {
+        let entries = shop.iter()
+            .chain(global)
+            .flat_map(|settings| settings.values.iter().map(|value| (normalize(value), settings.status)))
+            .filter(|(value, _)| !value.is_empty())
+            .collect();
+        AvailabilityMap { entries }
+    }
+
+    pub fn classify(&self, raw: &str) -> Availability {
+        let raw = normalize(raw);
+        if let Some((_, status)) = self.entries.iter().find(|(value, _)| *value == raw) {
+            return *status;
+        }
+
+        // Reversed so that, among equally long texts, the first one listed wins
+        self.entries.iter()
+            .rev()
+            .filter(|(value, _)| raw.contains(value.as_str()))
+            .max_by_key(|(value, _)| value.chars().count())
+            .map(|(_, status)| *status)
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,79 @@
+use std::sync::Arc;
+use async_trait::async_trait;
+use tokio::sync::Semaphore;
+use color_eyre::Report;
+use tracing::{info, warn, error, instrument};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use crate::types::Offer;
+use crate::telemetry::SpannedMessage;
+use crate::sink::{Delivery, OfferSink};
+use crate::parser::ScrapeError;
+use crate::http::{Fetch, FetchedPage};
+use crate::auth::BackendAuth;
+
+/// Posts every offer to the diceguild backend, one request per offer.
+pub struct BackendSink {
+    pub server_address: String,
+    pub post_endpoint: String,
+    client: Arc<dyn Fetch>,
+    /// Shared with the parsers, one permit per request
+    global_limit: Arc<Semaphore>,
+    auth: BackendAuth,
+}
+
+impl BackendSink {
+
+    pub fn new(server_address: &str, post_endpoint: &str, client: Arc<dyn Fetch>, global_limit: Arc<Semaphore>, auth: BackendAuth) -> BackendSink {
+        BackendSink {
+            server_address: server_address.to_owned(),
+            post_endpoint: post_endpoint.to_owned(),
+            client,
+            global_limit,
+            auth,
+        }
+    }
+
+    /// Posts anything to the backend, credentials included
+    pub(crate) async fn send(&self, url: &str, body: &serde_json::Value) -> Result<FetchedPage, ScrapeError> {
+        let bytes = serde_json::to_vec(body).map_err(|e| ScrapeError::Http(e.to_string()))?;
+        let headers = self.auth.headers(&bytes);
+        let _permit = self.global_limit.acquire().await.expect("Semaphore closed");
+        self.client.post_json(url, body, &headers).await
+    }
+
+    pub(crate) async fn post(&self, offer: &SpannedMessage<Offer>) -> Result<Delivery, ScrapeError> {
+        let post_url = format!("{}/{}", self.server_address, self.post_endpoint);
+        let body = serde_json::to_value(offer).map_err(|e| ScrapeError::Http(e.to_string()))?;
+        let response = self.send(&post_url, &body).await?;
+
+        if response.status == 515 {
+            warn!("Unable to match {:?}", offer.body());
+            return Ok(Delivery::Unmatched);
+        }
+        else if response.status != 200 {
+            // TODO: Fix this issue, but for now monitor it
+            if response.status == 408 {
+                tracing::Span::current().record("error_detail", "HttpTimeout");
+            }
+            error!("{} Failed to register {:?}", response.status, offer.body());
+            return Err(ScrapeError::BackendRejected(response.status));
+        }
+        else {
+            info!("Registered!");
+        }
+
+        Ok(Delivery::Accepted)
+    }
+}
+
+#[async_trait]
+impl OfferSink for BackendSink {
+
+    #[instrument(level = "info", name = "Publishing offer", skip_all, fields(error_detail="OK", shop=%offer.body().shop_name))]
+    async fn publish(&self, offer: &SpannedMessage<Offer>) -> Result<Delivery, Report> {
+        // Hang this span from the one that scraped the offer
+        tracing::Span::current().set_parent(offer.context().extract());
+
+        Ok(self.post(offer).await?)
+    }
+}
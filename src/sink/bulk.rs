@@ -0,0 +1,169 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use async_trait::async_trait;
+use color_eyre::{eyre::eyre, Report};
+use futures::future::join_all;
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::Instant;
+use tracing::{info, warn, instrument};
+use crate::types::Offer;
+use crate::telemetry::SpannedMessage;
+use crate::sink::{BackendSink, Delivery, OfferSink};
+use crate::parser::ScrapeError;
+
+// An offer waiting for its batch, and where to tell how it went
+type Pending = (SpannedMessage<Offer>, oneshot::Sender<Result<Delivery, Report>>);
+
+/* Posts offers to the backend in batches: an array of offers goes to the bulk
+ * endpoint once `batch_size` offers are waiting, or `max_wait` after the first
+ * one arrived. The backend answers 200 with an array holding the status of
+ * every offer, in the same order (200 posted, 515 unmatched...). An array of
+ * another length fails every offer of the batch, as there is no telling which
+ * one is which. Any other 200 body means every offer was taken.
+ *
+ * A 404, 405 or 501 means the backend knows nothing about bulk posting, so
+ * that batch and every later one go offer by offer through `BackendSink`.
+ */
+pub struct BulkBackendSink {
+    queue: mpsc::Sender<Pending>,
+}
+
+impl BulkBackendSink {
+
+    /// Spawns the task that puts batches together, so it needs a runtime
+    pub fn new(single: BackendSink, bulk_endpoint: &str, batch_size: usize, max_wait: Duration) -> BulkBackendSink {
+        let batch_size = batch_size.max(1);
+        let (queue, received) = mpsc::channel(batch_size * 2);
+        let batcher = Arc::new(Batcher {
+            bulk_url: format!("{}/{}", single.server_address, bulk_endpoint),
+            single,
+            unsupported: AtomicBool::new(false),
+        });
+        tokio::spawn(batcher.run(received, batch_size, max_wait));
+
+        BulkBackendSink { queue }
+    }
+}
+
+struct Batcher {
+    bulk_url: String,
+    single: BackendSink,
+    unsupported: AtomicBool,
+}
+
+impl Batcher {
+
+    // Batches are sent in their own task, so the next one fills up meanwhile.
+    // Every request takes a global permit anyway.
+    async fn run(self: Arc<Self>, mut received: mpsc::Receiver<Pending>, batch_size: usize, max_wait: Duration) {
+        while let Some(first) = received.recv().await {
+            let deadline = Instant::now() + max_wait;
+            let mut batch = vec![first];
+            while batch.len() < batch_size {
+                match tokio::time::timeout_at(deadline, received.recv()).await {
+                    Ok(Some(pending)) => batch.push(pending),
+                    Ok(None) | Err(_) => break,
+                }
+            }
+            let batcher = self.clone();
+            tokio::spawn(async move { batcher.send(batch).await });
+        }
+    }
+
+    async fn send(&self, batch: Vec<Pending>) {
+        let batch = match self.unsupported.load(Ordering::Relaxed) {
+            true => batch,
+            false => match self.post_batch(batch).await {
+                Some(batch) => batch,
+                None => return,
+            },
+        };
+
+        // Offer by offer, each one in its own span
+        join_all(batch.into_iter().map(|(offer, reply)| async move {
+            let _ = reply.send(self.single.publish(&offer).await);
+        })).await;
+    }
+
+    // Gives the batch back when it has to go offer by offer
+    #[instrument(level = "info", name = "Publishing offers", skip_all, fields(error_detail="OK", offers=batch.len()))]
+    async fn post_batch(&self, batch: Vec<Pending>) -> Option<Vec<Pending>> {
+        let offers: Vec<&SpannedMessage<Offer>> = batch.iter().map(|(offer, _)| offer).collect();
+        let body = match serde_json::to_value(&offers) {
+            Ok(body) => body,
+            Err(e) => {
+                let error = e.to_string();
+                for (_, reply) in batch {
+                    let _ = reply.send(Err(eyre!("Unable to serialize the batch: {}", error)));
+                }
+                return None;
+            }
+        };
+
+        let response = match self.single.send(&self.bulk_url, &body).await {
+            Ok(response) => response,
+            Err(e) => {
+                tracing::Span::current().record("error_detail", e.kind().as_str());
+                for (_, reply) in batch {
+                    let _ = reply.send(Err(e.clone().into()));
+                }
+                return None;
+            }
+        };
+
+        match response.status {
+            200 => {
+                let statuses: Vec<u16> = match serde_json::from_str::<Vec<u16>>(&response.body) {
+                    Ok(statuses) if statuses.len() != batch.len() => {
+                        let error = format!("{} answered {} statuses for {} offers", self.bulk_url, statuses.len(), batch.len());
+                        warn!("{}", error);
+                        for (_, reply) in batch {
+                            let _ = reply.send(Err(eyre!("{}", error)));
+                        }
+                        return None;
+                    }
+                    Ok(statuses) => statuses,
+                    Err(_) => vec![200; batch.len()],
+                };
+                info!("Registered {} offers", batch.len());
+                for ((offer, reply), status) in batch.into_iter().zip(statuses) {
+                    let _ = reply.send(match status {
+                        200 => Ok(Delivery::Accepted),
+                        515 => {
+                            warn!("Unable to match {:?}", offer.body());
+                            Ok(Delivery::Unmatched)
+                        }
+                        status => Err(ScrapeError::BackendRejected(status).into()),
+                    });
+                }
+                None
+            }
+            404 | 405 | 501 => {
+                warn!("{} does not take batches ({}), posting offers one by one", self.bulk_url, response.status);
+                self.unsupported.store(true, Ordering::Relaxed);
+                Some(batch)
+            }
+            status => {
+                if status == 408 {
+                    tracing::Span::current().record("error_detail", "HttpTimeout");
+                }
+                warn!("{} Failed to register {} offers", status, batch.len());
+                for (_, reply) in batch {
+                    let _ = reply.send(Err(ScrapeError::BackendRejected(status).into()));
+                }
+                None
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl OfferSink for BulkBackendSink {
+
+    async fn publish(&self, offer: &SpannedMessage<Offer>) -> Result<Delivery, Report> {
+        let (reply, delivery) = oneshot::channel();
+        self.queue.send((offer.clone(), reply)).await.map_err(|_| eyre!("Bulk publisher stopped"))?;
+        delivery.await.map_err(|_| eyre!("Bulk publisher stopped"))?
+    }
+}
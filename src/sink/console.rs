@@ -0,0 +1,54 @@
+use std::io::Write;
+use std::sync::Mutex;
+use async_trait::async_trait;
+use color_eyre::Report;
+use crate::types::{Offer, Rejection};
+use crate::telemetry::SpannedMessage;
+use crate::sink::{Delivery, OfferSink};
+
+/// Prints every offer, and every rejected entry with the rule that dropped
+/// it, for `--dry-run`. Nothing leaves the machine.
+pub struct ConsoleSink {
+    output: Mutex<Box<dyn Write + Send>>,
+}
+
+impl ConsoleSink {
+
+    /// Prints to `output`, one line per offer or rejection
+    pub fn new(output: Box<dyn Write + Send>) -> ConsoleSink {
+        ConsoleSink { output: Mutex::new(output) }
+    }
+
+    /// Prints to the terminal, logs go to stderr meanwhile
+    pub fn stdout() -> ConsoleSink {
+        ConsoleSink::new(Box::new(std::io::stdout()))
+    }
+}
+
+#[async_trait]
+impl OfferSink for ConsoleSink {
+
+    async fn publish(&self, offer: &SpannedMessage<Offer>) -> Result<Delivery, Report> {
+        let offer = offer.body();
+        let rules = match offer.name_rules.is_empty() {
+            true => String::new(),
+            false => format!(" (was `{}`, rules: {})", offer.raw_name, offer.name_rules.join(", ")),
+        };
+        let language = offer.language.as_deref().unwrap_or("?");
+        writeln!(
+            self.output.lock().unwrap(),
+            "OFFER    {} | {}{} | {}, {}, {} | {} (normal {}) | {} ({}) | {}",
+            offer.shop_name, offer.name, rules, offer.product_type, language, offer.condition, offer.offer_price, offer.normal_price, offer.availability, offer.raw_availability, offer.url
+        )?;
+        Ok(Delivery::Accepted)
+    }
+
+    async fn reject(&self, rejection: &Rejection) -> Result<(), Report> {
+        writeln!(
+            self.output.lock().unwrap(),
+            "REJECTED {} | {} | rule: {} | {}",
+            rejection.shop_name, rejection.raw_name, rejection.rule, rejection.url
+        )?;
+        Ok(())
+    }
+}
@@ -0,0 +1,88 @@
+use std::fs::File;
+use std::path::Path;
+use std::sync::Mutex;
+use async_trait::async_trait;
+use chrono::SecondsFormat;
+use color_eyre::Report;
+use rust_decimal::Decimal;
+use crate::types::Offer;
+use crate::telemetry::SpannedMessage;
+use crate::sink::{Delivery, OfferSink};
+
+/* Column order is part of the format, spreadsheets out there depend on it.
+ * Add new columns at the end, never in the middle.
+ *
+ *  shop          shop_name of the offer
+ *  name          name after the name rules
+ *  url           product page
+ *  normal_price  "1234.50": dot as decimal separator, two decimals, no
+ *                thousands separator nor currency, whatever the shop uses
+ *  offer_price   same as normal_price
+ *  discount      percentage off normal_price, same format ("15.00")
+ *  availability  as the shop tells it
+ *  scraped_at    RFC 3339 in UTC, to the second
+ *  currency      ISO 4217 code of both prices
+ *  savings       normal_price - offer_price, same format
+ *  run_id        run that scraped the offer
+ *  status        availability mapped to in_stock, low_stock, out_of_stock,
+ *                preorder, on_demand or unknown
+ *  language      edition language ("es"), empty when the name does not tell
+ *  condition     new, like_new or damaged_box
+ *  product_type  base_game, expansion, promo, preorder, bundle or accessory
+ */
+const COLUMNS: [&str; 15] = [
+    "shop", "name", "url", "normal_price", "offer_price", "discount", "availability", "scraped_at",
+    "currency", "savings", "run_id", "status",
+    "language", "condition", "product_type",
+];
+
+fn format_amount(amount: Decimal) -> String {
+    format!("{:.2}", amount.round_dp(2))
+}
+
+/// Writes every offer as a row of a CSV file, see `COLUMNS`.
+pub struct CsvSink {
+    writer: Mutex<csv::Writer<File>>,
+}
+
+impl CsvSink {
+
+    /// Creates the file, replacing any previous one, with the header row
+    pub fn new(path: &Path) -> Result<CsvSink, Report> {
+        let mut writer = csv::Writer::from_path(path)?;
+        writer.write_record(COLUMNS)?;
+        writer.flush()?;
+        Ok(CsvSink { writer: Mutex::new(writer) })
+    }
+}
+
+#[async_trait]
+impl OfferSink for CsvSink {
+
+    async fn publish(&self, offer: &SpannedMessage<Offer>) -> Result<Delivery, Report> {
+        let offer = offer.body();
+        let row = [
+            offer.shop_name.clone(),
+            offer.name.clone(),
+            offer.url.clone(),
+            format_amount(offer.normal_price.amount),
+            format_amount(offer.offer_price.amount),
+            format_amount(offer.discount_percent),
+            offer.raw_availability.clone(),
+            offer.scraped_at.to_rfc3339_opts(SecondsFormat::Secs, true),
+            offer.currency.code().to_string(),
+            format_amount(offer.savings.amount),
+            offer.run_id.clone(),
+            offer.availability.to_string(),
+            offer.language.clone().unwrap_or_default(),
+            offer.condition.to_string(),
+            offer.product_type.to_string(),
+        ];
+
+        // Flushed row by row, a broken run still leaves a usable file
+        let mut writer = self.writer.lock().unwrap();
+        writer.write_record(&row)?;
+        writer.flush()?;
+        Ok(Delivery::Accepted)
+    }
+}
@@ -0,0 +1,43 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+use async_trait::async_trait;
+use color_eyre::Report;
+use crate::types::Offer;
+use crate::telemetry::SpannedMessage;
+use crate::sink::{Delivery, OfferSink};
+
+/// Writes every offer as a line of JSON, for jq and notebooks to chew on.
+pub struct JsonLinesSink {
+    output: Mutex<Box<dyn Write + Send>>,
+}
+
+impl JsonLinesSink {
+
+    /// Creates the file, replacing any previous one
+    pub fn new(path: &Path) -> Result<JsonLinesSink, Report> {
+        Ok(JsonLinesSink { output: Mutex::new(Box::new(File::create(path)?)) })
+    }
+
+    /// Streams the offers as they come, for shell pipelines
+    pub fn stdout() -> JsonLinesSink {
+        JsonLinesSink { output: Mutex::new(Box::new(std::io::stdout())) }
+    }
+}
+
+#[async_trait]
+impl OfferSink for JsonLinesSink {
+
+    async fn publish(&self, offer: &SpannedMessage<Offer>) -> Result<Delivery, Report> {
+        // Offers are written as soon as they are scraped
+        let mut line = serde_json::to_vec(offer.body())?;
+        line.push(b'\n');
+
+        // A single write per line so concurrent offers never mix
+        let mut output = self.output.lock().unwrap();
+        output.write_all(&line)?;
+        output.flush()?;
+        Ok(Delivery::Accepted)
+    }
+}
@@ -0,0 +1,19 @@
+mod offer_sink;
+mod backend;
+mod bulk;
+mod json_lines;
+mod csv_file;
+mod outbox;
+mod console;
+mod unmatched;
+mod webhook;
+
+pub use offer_sink::{Delivery, OfferSink, FanOutSink};
+pub use backend::BackendSink;
+pub use bulk::BulkBackendSink;
+pub use json_lines::JsonLinesSink;
+pub use csv_file::CsvSink;
+pub use outbox::OutboxSink;
+pub use console::ConsoleSink;
+pub use unmatched::UnmatchedSink;
+pub use webhook::WebhookSink;
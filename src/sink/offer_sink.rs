@@ -0,0 +1,70 @@
+use async_trait::async_trait;
+use color_eyre::Report;
+use crate::types::{Offer, Rejection};
+use crate::telemetry::SpannedMessage;
+
+/// What happened to an offer a sink took
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delivery {
+    Accepted,
+    /// Taken, but the backend could not match it to any known game (HTTP 515)
+    Unmatched,
+}
+
+/// Destination for the offers produced by a `ShopParser`.
+///
+/// Parsers only scrape; whatever happens to an offer afterwards (posting it to
+/// the backend, writing it to disk, printing it...) is the job of a sink.
+#[async_trait]
+pub trait OfferSink: Send + Sync {
+    async fn publish(&self, offer: &SpannedMessage<Offer>) -> Result<Delivery, Report>;
+
+    /// Called for every entry a name rule kept from being published. Most
+    /// sinks do not care about them.
+    async fn reject(&self, _rejection: &Rejection) -> Result<(), Report> {
+        Ok(())
+    }
+}
+
+/// Sends every offer to all the wrapped sinks, in order. A failing sink does
+/// not prevent the rest from receiving the offer. The offer only counts as
+/// accepted when every sink accepted it.
+#[derive(Default)]
+pub struct FanOutSink {
+    sinks: Vec<Box<dyn OfferSink>>,
+}
+
+impl FanOutSink {
+    pub fn new(sinks: Vec<Box<dyn OfferSink>>) -> FanOutSink {
+        FanOutSink { sinks }
+    }
+}
+
+#[async_trait]
+impl OfferSink for FanOutSink {
+    async fn publish(&self, offer: &SpannedMessage<Offer>) -> Result<Delivery, Report> {
+        let mut result = Ok(Delivery::Accepted);
+        for sink in &self.sinks {
+            match sink.publish(offer).await {
+                Ok(Delivery::Accepted) => (),
+                Ok(Delivery::Unmatched) => {
+                    if result.is_ok() {
+                        result = Ok(Delivery::Unmatched);
+                    }
+                }
+                Err(e) => result = Err(e),
+            }
+        }
+        result
+    }
+
+    async fn reject(&self, rejection: &Rejection) -> Result<(), Report> {
+        let mut result = Ok(());
+        for sink in &self.sinks {
+            if let Err(e) = sink.reject(rejection).await {
+                result = Err(e);
+            }
+        }
+        result
+    }
+}
@@ -0,0 +1,41 @@
+use std::sync::Arc;
+use async_trait::async_trait;
+use color_eyre::Report;
+use crate::types::{Offer, Rejection};
+use crate::telemetry::SpannedMessage;
+use crate::sink::{Delivery, OfferSink};
+use crate::outbox::Outbox;
+use crate::parser::ScrapeError;
+
+/// Keeps in the outbox whatever `inner` failed to deliver for reasons that may
+/// go away by the next run. The failure is still reported.
+pub struct OutboxSink {
+    inner: Box<dyn OfferSink>,
+    outbox: Arc<Outbox>,
+}
+
+impl OutboxSink {
+    pub fn new(inner: Box<dyn OfferSink>, outbox: Arc<Outbox>) -> OutboxSink {
+        OutboxSink { inner, outbox }
+    }
+}
+
+#[async_trait]
+impl OfferSink for OutboxSink {
+
+    async fn publish(&self, offer: &SpannedMessage<Offer>) -> Result<Delivery, Report> {
+        let delivery = self.inner.publish(offer).await;
+        if let Err(e) = &delivery {
+            if e.downcast_ref::<ScrapeError>().is_some_and(ScrapeError::is_transient) {
+                if let Err(spool_error) = self.outbox.push(offer, &e.to_string()) {
+                    tracing::error!("Unable to keep {} in the outbox: {}", offer.body().url, spool_error);
+                }
+            }
+        }
+        delivery
+    }
+
+    async fn reject(&self, rejection: &Rejection) -> Result<(), Report> {
+        self.inner.reject(rejection).await
+    }
+}
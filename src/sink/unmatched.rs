@@ -0,0 +1,38 @@
+use async_trait::async_trait;
+use color_eyre::Report;
+use crate::types::{Offer, Rejection};
+use crate::telemetry::SpannedMessage;
+use crate::sink::{Delivery, OfferSink};
+use crate::store::Store;
+
+/// Keeps every offer `inner` could not match (515) in the store for review,
+/// see `Store::top_unmatched`.
+pub struct UnmatchedSink {
+    inner: Box<dyn OfferSink>,
+    store: Store,
+}
+
+impl UnmatchedSink {
+    pub fn new(inner: Box<dyn OfferSink>, store: Store) -> UnmatchedSink {
+        UnmatchedSink { inner, store }
+    }
+}
+
+#[async_trait]
+impl OfferSink for UnmatchedSink {
+
+    async fn publish(&self, offer: &SpannedMessage<Offer>) -> Result<Delivery, Report> {
+        let delivery = self.inner.publish(offer).await;
+        if let Ok(Delivery::Unmatched) = delivery {
+            let unmatched = offer.body().clone();
+            if let Err(e) = self.store.in_background(move |store| store.record_unmatched(&unmatched)).await {
+                tracing::error!("Unable to keep {} for review: {}", offer.body().url, e);
+            }
+        }
+        delivery
+    }
+
+    async fn reject(&self, rejection: &Rejection) -> Result<(), Report> {
+        self.inner.reject(rejection).await
+    }
+}
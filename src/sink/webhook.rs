@@ -0,0 +1,160 @@
+use std::sync::Arc;
+use std::time::Duration;
+use async_trait::async_trait;
+use chrono::SecondsFormat;
+use color_eyre::{eyre::eyre, Report};
+use rust_decimal::Decimal;
+use tokio::sync::Semaphore;
+use tracing::{info, warn};
+use crate::configuration::WebhookSettings;
+use crate::types::Offer;
+use crate::telemetry::SpannedMessage;
+use crate::sink::{Delivery, OfferSink};
+use crate::parser::ScrapeError;
+use crate::http::Fetch;
+
+/* Sends the offers that pass its filters to any HTTP endpoint taking JSON,
+ * such as Discord, Slack or Mattermost incoming webhooks.
+ *
+ * The body comes from `template`, where `{{field}}` is replaced by the field
+ * of the offer, escaped to sit inside a JSON string:
+ *   {"content": "{{name}} for {{offer_price}} {{currency}} at {{shop}}"}
+ * Fields: shop, name, raw_name, url, offer_price, normal_price, currency,
+ * discount, savings, availability (as the shop tells it), status (see
+ * `Availability`), language (empty when unknown), condition, product_type,
+ * scraped_at and run_id, plus ean, publisher and image_url in runs with
+ * `--details` (empty otherwise).
+ *
+ * Webhooks are notifications: failures are logged but the offer still counts
+ * as delivered.
+ */
+pub struct WebhookSink {
+    settings: WebhookSettings,
+    url: String,
+    /// `name` of every shop in `settings.shops`, empty for all of them
+    shop_names: Vec<String>,
+    client: Arc<dyn Fetch>,
+    global_limit: Arc<Semaphore>,
+}
+
+impl WebhookSink {
+
+    /// Checks the template right away, better to fail before scraping
+    pub fn new(settings: WebhookSettings, shop_names: Vec<String>, client: Arc<dyn Fetch>, global_limit: Arc<Semaphore>) -> Result<WebhookSink, Report> {
+        let url = WebhookSink::url(&settings)?;
+
+        // Fields go inside JSON strings, where a quote is fine and nowhere else
+        fill(&settings.template, |name| FIELDS.iter().any(|(field, _)| *field == name).then(|| String::from("\"")))
+            .map_err(|e| eyre!("Webhook `{}`: {}", settings.id, e))?;
+
+        Ok(WebhookSink { settings, url, shop_names, client, global_limit })
+    }
+
+    /// Where `settings` posts to, which may hold a token
+    pub fn url(settings: &WebhookSettings) -> Result<String, Report> {
+        match &settings.url_env {
+            Some(variable) => std::env::var(variable).map_err(|_| eyre!("Webhook `{}` needs the {} variable", settings.id, variable)),
+            None => settings.url.clone().ok_or_else(|| eyre!("Webhook `{}` has no url", settings.id)),
+        }
+    }
+
+    fn wants(&self, offer: &Offer) -> bool {
+        (self.shop_names.is_empty() || self.shop_names.contains(&offer.shop_name))
+            && (self.settings.product_types.is_empty() || self.settings.product_types.contains(&offer.product_type))
+            && offer.discount_percent >= Decimal::from(self.settings.min_discount)
+    }
+
+    async fn send(&self, body: &serde_json::Value) -> Result<(), ScrapeError> {
+        let mut attempt = 0;
+        loop {
+            let response = {
+                let _permit = self.global_limit.acquire().await.expect("Semaphore closed");
+                self.client.post_json(&self.url, body, &[]).await
+            };
+            let error = match response {
+                Ok(response) if (200..300).contains(&response.status) => return Ok(()),
+                Ok(response) => ScrapeError::HttpStatus(response.status),
+                Err(e) => e,
+            };
+
+            // 429 is the usual answer of chat services under load
+            let retry = error.is_transient() || error == ScrapeError::HttpStatus(429);
+            if !retry || attempt >= self.settings.retries {
+                return Err(error);
+            }
+            attempt += 1;
+            warn!("Webhook `{}` failed ({}), retry {} of {}", self.settings.id, error, attempt, self.settings.retries);
+            tokio::time::sleep(Duration::from_secs(self.settings.retry_delay_secs * attempt as u64)).await;
+        }
+    }
+}
+
+// Value of a template field for an offer
+type FieldValue = fn(&Offer) -> String;
+
+// Every `{{field}}` a template can use
+static FIELDS: &[(&str, FieldValue)] = &[
+    ("shop", |offer| offer.shop_name.clone()),
+    ("name", |offer| offer.name.clone()),
+    ("raw_name", |offer| offer.raw_name.clone()),
+    ("url", |offer| offer.url.clone()),
+    ("offer_price", |offer| format!("{:.2}", offer.offer_price.amount)),
+    ("normal_price", |offer| format!("{:.2}", offer.normal_price.amount)),
+    ("currency", |offer| offer.currency.code().to_string()),
+    ("discount", |offer| format!("{:.0}", offer.discount_percent)),
+    ("savings", |offer| format!("{:.2}", offer.savings.amount)),
+    ("availability", |offer| offer.raw_availability.clone()),
+    ("status", |offer| offer.availability.to_string()),
+    ("language", |offer| offer.language.clone().unwrap_or_default()),
+    ("condition", |offer| offer.condition.to_string()),
+    ("product_type", |offer| offer.product_type.to_string()),
+    ("scraped_at", |offer| offer.scraped_at.to_rfc3339_opts(SecondsFormat::Secs, true)),
+    ("run_id", |offer| offer.run_id.clone()),
+    ("ean", |offer| offer.details.ean.clone().unwrap_or_default()),
+    ("publisher", |offer| offer.details.publisher.clone().unwrap_or_default()),
+    ("image_url", |offer| offer.details.image_url.clone().unwrap_or_default()),
+];
+
+fn render(template: &str, offer: &Offer) -> Result<serde_json::Value, String> {
+    fill(template, |name| FIELDS.iter().find(|(field, _)| *field == name).map(|(_, value)| value(offer)))
+}
+
+// Replaces every placeholder in a single pass, so whatever a value holds,
+// `{{` included, is never taken for another placeholder
+fn fill(template: &str, value: impl Fn(&str) -> Option<String>) -> Result<serde_json::Value, String> {
+    let mut body = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        body.push_str(&rest[..start]);
+        let length = rest[start..].find("}}").ok_or("unclosed `{{` in template")?;
+        let field = &rest[start + 2..start + length];
+        let value = value(field).ok_or_else(|| format!("unknown field `{{{{{}}}}}` in template", field))?;
+        // Escaped as a JSON string, without the quotes around it
+        let escaped = serde_json::to_string(&value).map_err(|e| e.to_string())?;
+        body.push_str(&escaped[1..escaped.len() - 1]);
+        rest = &rest[start + length + 2..];
+    }
+    body.push_str(rest);
+    serde_json::from_str(&body).map_err(|e| format!("template is not valid JSON once filled: {}", e))
+}
+
+#[async_trait]
+impl OfferSink for WebhookSink {
+
+    async fn publish(&self, offer: &SpannedMessage<Offer>) -> Result<Delivery, Report> {
+        let offer = offer.body();
+        if !self.wants(offer) {
+            return Ok(Delivery::Accepted);
+        }
+
+        let sent = match render(&self.settings.template, offer) {
+            Ok(body) => self.send(&body).await.map_err(|e| e.to_string()),
+            Err(e) => Err(e),
+        };
+        match sent {
+            Ok(()) => info!("Webhook `{}` notified of {}", self.settings.id, offer.url),
+            Err(e) => tracing::error!("Webhook `{}` failed for {}: {}", self.settings.id, offer.url, e),
+        }
+        Ok(Delivery::Accepted)
+    }
+}
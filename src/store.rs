@@ -0,0 +1,217 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use async_trait::async_trait;
+use chrono::{SecondsFormat, Utc};
+use color_eyre::Report;
+use rusqlite::{params, Connection};
+use crate::types::Offer;
+use crate::telemetry::SpannedMessage;
+use crate::sink::{Delivery, OfferSink};
+
+/* Local history of every offer seen, whatever the backend thought of it.
+ *
+ * `products` has one row per shop and canonical URL, `observations` one row
+ * per product and run, with the prices as exact decimal strings ("1234.50"),
+ * the batch the listing was scraped in, the availability as the shop tells it
+ * and its `status` (`Availability`), and the language, condition and product
+ * type the name tells.
+ *
+ * `unmatched` keeps the offers the backend could not match with any game
+ * (515), once per shop, URL and name, counting how many times it happened.
+ */
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS products (
+        id INTEGER PRIMARY KEY,
+        shop TEXT NOT NULL,
+        url TEXT NOT NULL,
+        name TEXT NOT NULL,
+        first_seen TEXT NOT NULL,
+        last_seen TEXT NOT NULL,
+        UNIQUE (shop, url)
+    );
+    CREATE TABLE IF NOT EXISTS observations (
+        id INTEGER PRIMARY KEY,
+        product_id INTEGER NOT NULL REFERENCES products (id),
+        run_id TEXT NOT NULL,
+        batch TEXT NOT NULL,
+        observed_at TEXT NOT NULL,
+        normal_price TEXT NOT NULL,
+        offer_price TEXT NOT NULL,
+        currency TEXT NOT NULL,
+        availability TEXT NOT NULL,
+        status TEXT NOT NULL DEFAULT 'unknown',
+        language TEXT,
+        condition TEXT NOT NULL DEFAULT 'new',
+        product_type TEXT NOT NULL DEFAULT 'base_game'
+    );
+    CREATE INDEX IF NOT EXISTS observations_by_product ON observations (product_id, batch);
+    CREATE TABLE IF NOT EXISTS unmatched (
+        id INTEGER PRIMARY KEY,
+        shop TEXT NOT NULL,
+        url TEXT NOT NULL,
+        raw_name TEXT NOT NULL,
+        name TEXT NOT NULL,
+        first_seen TEXT NOT NULL,
+        last_seen TEXT NOT NULL,
+        times_seen INTEGER NOT NULL,
+        UNIQUE (shop, url, name)
+    );
+";
+
+// Query parameters that only tell where the visitor came from, on top of `utm_*`
+const TRACKING_PARAMETERS: &[&str] = &["fbclid", "gclid", "dclid", "msclkid", "yclid", "mc_cid", "mc_eid", "_ga"];
+
+fn is_tracking(parameter: &str) -> bool {
+    let name = parameter.split('=').next().unwrap_or_default().to_lowercase();
+    name.starts_with("utm_") || TRACKING_PARAMETERS.contains(&name.as_str())
+}
+
+/// The same product can be linked with tracking parameters, anchors, a
+/// trailing slash or an uppercase host. None of that makes it another product,
+/// unlike the rest of the query (`index.php?id_product=12`).
+pub fn canonical_url(url: &str) -> String {
+    let url = url.split('#').next().unwrap_or_default();
+    let (url, query) = url.split_once('?').unwrap_or((url, ""));
+    let query: Vec<&str> = query.split('&').filter(|parameter| !parameter.is_empty() && !is_tracking(parameter)).collect();
+
+    let url = url.trim_end_matches('/');
+    let url = match url.split_once("://") {
+        Some((scheme, rest)) => {
+            let (host, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+            format!("{}://{}{}", scheme.to_lowercase(), host.to_lowercase(), path)
+        }
+        None => url.to_string(),
+    };
+    match query.is_empty() {
+        true => url,
+        false => format!("{}?{}", url, query.join("&")),
+    }
+}
+
+// `CREATE TABLE IF NOT EXISTS` leaves alone tables from older versions, which
+// may lack columns added since
+fn add_column(connection: &Connection, table: &str, column: &str, definition: &str) -> Result<(), Report> {
+    let exists = connection.prepare(&format!("SELECT * FROM {}", table))?
+        .column_names()
+        .contains(&column);
+    if !exists {
+        connection.execute_batch(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition))?;
+    }
+    Ok(())
+}
+
+/// A name the backend keeps failing to match
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnmatchedTitle {
+    pub name: String,
+    pub times_seen: usize,
+    pub shops: usize,
+    /// One of the names as the shops list it
+    pub raw_name: String,
+}
+
+/// SQLite database with the price history, see `SCHEMA`. Clones share the
+/// same connection.
+#[derive(Clone)]
+pub struct Store {
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl Store {
+
+    /// Opens the database, creating it and its tables when needed
+    pub fn open(path: &Path) -> Result<Store, Report> {
+        let connection = Connection::open(path)?;
+        connection.execute_batch(SCHEMA)?;
+        add_column(&connection, "observations", "status", "TEXT NOT NULL DEFAULT 'unknown'")?;
+        add_column(&connection, "observations", "language", "TEXT")?;
+        add_column(&connection, "observations", "condition", "TEXT NOT NULL DEFAULT 'new'")?;
+        add_column(&connection, "observations", "product_type", "TEXT NOT NULL DEFAULT 'base_game'")?;
+        Ok(Store { connection: Arc::new(Mutex::new(connection)) })
+    }
+
+    /// Upserts the product and adds an observation, in the run and at the
+    /// time the offer was scraped
+    pub fn record(&self, offer: &Offer) -> Result<(), Report> {
+        let now = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+        let observed_at = offer.scraped_at.to_rfc3339_opts(SecondsFormat::Secs, true);
+        let mut connection = self.connection.lock().unwrap();
+        let transaction = connection.transaction()?;
+
+        let product_id: i64 = transaction.query_row(
+            "INSERT INTO products (shop, url, name, first_seen, last_seen) VALUES (?1, ?2, ?3, ?4, ?4)
+             ON CONFLICT (shop, url) DO UPDATE SET name = excluded.name, last_seen = excluded.last_seen
+             RETURNING id",
+            params![offer.shop_name, canonical_url(&offer.url), offer.name, now],
+            |row| row.get(0),
+        )?;
+        transaction.execute(
+            "INSERT INTO observations (product_id, run_id, batch, observed_at, normal_price, offer_price, currency, availability, status, language, condition, product_type)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            params![
+                product_id,
+                offer.run_id,
+                offer.batch,
+                observed_at,
+                offer.normal_price.amount.to_string(),
+                offer.offer_price.amount.to_string(),
+                offer.currency.code(),
+                offer.raw_availability,
+                offer.availability.as_str(),
+                offer.language,
+                offer.condition.as_str(),
+                offer.product_type.as_str(),
+            ],
+        )?;
+
+        transaction.commit()?;
+        Ok(())
+    }
+
+    pub fn record_unmatched(&self, offer: &Offer) -> Result<(), Report> {
+        let now = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+        self.connection.lock().unwrap().execute(
+            "INSERT INTO unmatched (shop, url, raw_name, name, first_seen, last_seen, times_seen) VALUES (?1, ?2, ?3, ?4, ?5, ?5, 1)
+             ON CONFLICT (shop, url, name) DO UPDATE SET raw_name = excluded.raw_name, last_seen = excluded.last_seen, times_seen = times_seen + 1",
+            params![offer.shop_name, canonical_url(&offer.url), offer.raw_name, offer.name, now],
+        )?;
+        Ok(())
+    }
+
+    /// Runs `job` on the blocking thread pool. SQLite calls block, and there
+    /// can be hundreds of offers being published on the async workers.
+    pub(crate) async fn in_background<T, F>(&self, job: F) -> Result<T, Report>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Store) -> Result<T, Report> + Send + 'static,
+    {
+        let store = self.clone();
+        tokio::task::spawn_blocking(move || job(&store)).await?
+    }
+
+    /// Names the backend failed to match, most frequent first
+    pub fn top_unmatched(&self, limit: usize) -> Result<Vec<UnmatchedTitle>, Report> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection.prepare(
+            "SELECT name, SUM(times_seen), COUNT(DISTINCT shop), MAX(raw_name) FROM unmatched
+             GROUP BY name ORDER BY SUM(times_seen) DESC, name LIMIT ?1",
+        )?;
+        let titles = statement.query_map(params![limit as i64], |row| Ok(UnmatchedTitle {
+            name: row.get(0)?,
+            times_seen: row.get::<_, i64>(1)? as usize,
+            shops: row.get::<_, i64>(2)? as usize,
+            raw_name: row.get(3)?,
+        }))?;
+        Ok(titles.collect::<Result<_, _>>()?)
+    }
+}
+
+#[async_trait]
+impl OfferSink for Store {
+
+    async fn publish(&self, offer: &SpannedMessage<Offer>) -> Result<Delivery, Report> {
+        let offer = offer.body().clone();
+        self.in_background(move |store| store.record(&offer)).await?;
+        Ok(Delivery::Accepted)
+    }
+}
@@ -6,6 +6,7 @@ use opentelemetry::sdk::{trace, Resource};
 use opentelemetry_otlp::WithExportConfig;
 use tracing_bunyan_formatter::{BunyanFormattingLayer, JsonStorageLayer};
 use tracing_subscriber::Registry;
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
 use tracing_subscriber::{prelude::*, EnvFilter};
 
 use opentelemetry::{
@@ -14,7 +15,8 @@ use opentelemetry::{
 use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 
-pub fn init_telemetry(exporter_endpoint: &str, service_name: &str) {
+/// Logs go to stdout unless `logs_to_stderr`, for when stdout carries data
+pub fn init_telemetry(exporter_endpoint: &str, service_name: &str, logs_to_stderr: bool) {
     // Create a gRPC exporter
     let exporter = opentelemetry_otlp::new_exporter()
         .tonic()
@@ -37,10 +39,14 @@ pub fn init_telemetry(exporter_endpoint: &str, service_name: &str) {
     let level_filter_layer = EnvFilter::try_from_default_env().unwrap_or(EnvFilter::new("INFO"));
     // Layer for adding our configured tracer.
     let tracing_layer = tracing_opentelemetry::layer().with_tracer(tracer);
-    // Layer for printing spans to stdout
+    // Layer for printing spans to stdout (or stderr)
+    let writer = match logs_to_stderr {
+        true => BoxMakeWriter::new(std::io::stderr),
+        false => BoxMakeWriter::new(std::io::stdout),
+    };
     let formatting_layer = BunyanFormattingLayer::new(
         service_name.to_string(),
-        std::io::stdout,
+        writer,
     );
     global::set_text_map_propagator(TraceContextPropagator::new());
 
@@ -97,6 +103,10 @@ impl<T: core::fmt::Debug + Clone> SpannedMessage<T> {
         self.body
     }
 
+    pub fn body(&self) -> &T {
+        &self.body
+    }
+
     pub fn context(&self) -> &PropagationContext {
         &self.context
     }
@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+
+/// Stock status, whatever words each shop uses for it. See
+/// `rules::AvailabilityMap` for how the raw text is mapped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Availability {
+    InStock,
+    /// Still in stock, but not for long
+    LowStock,
+    OutOfStock,
+    /// Not released yet, can be reserved
+    Preorder,
+    /// Ordered from the distributor once bought
+    OnDemand,
+    /// No text, or a text no table knows. Anything unexpected when reading
+    /// old offers ends here too.
+    #[default]
+    #[serde(other)]
+    Unknown,
+}
+
+impl Availability {
+
+    /// Same name serde gives it, used in CSV files, webhooks and the database
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Availability::InStock => "in_stock",
+            Availability::LowStock => "low_stock",
+            Availability::OutOfStock => "out_of_stock",
+            Availability::Preorder => "preorder",
+            Availability::OnDemand => "on_demand",
+            Availability::Unknown => "unknown",
+        }
+    }
+}
+
+impl std::fmt::Display for Availability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+
+/// State of the item itself, as far as the name tells
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Condition {
+    /// Also what a name without any mark gets
+    #[default]
+    New,
+    /// Second-hand but complete and in good shape, "seminuevo"
+    LikeNew,
+    /// New contents in a dented or torn box
+    DamagedBox,
+}
+
+impl Condition {
+
+    /// Its serde name, kept by hand so it never costs an allocation
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Condition::New => "new",
+            Condition::LikeNew => "like_new",
+            Condition::DamagedBox => "damaged_box",
+        }
+    }
+}
+
+impl std::fmt::Display for Condition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
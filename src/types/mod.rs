@@ -1,3 +1,12 @@
+#[allow(clippy::module_inception)]
 mod types;
+mod money;
+mod availability;
+mod condition;
+mod product_type;
 
 pub use types::*;
+pub use money::*;
+pub use availability::*;
+pub use condition::*;
+pub use product_type::*;
@@ -0,0 +1,70 @@
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Currency {
+    #[default]
+    Eur,
+    Usd,
+    Gbp,
+}
+
+impl Currency {
+
+    /// ISO 4217 code
+    pub fn code(&self) -> &'static str {
+        match self {
+            Currency::Eur => "EUR",
+            Currency::Usd => "USD",
+            Currency::Gbp => "GBP",
+        }
+    }
+
+    /// Currency for a symbol or code as found in a price tag
+    pub fn from_symbol(symbol: &str) -> Option<Currency> {
+        match symbol.trim().to_uppercase().as_str() {
+            "€" | "EUR" => Some(Currency::Eur),
+            "$" | "USD" => Some(Currency::Usd),
+            "£" | "GBP" => Some(Currency::Gbp),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Currency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+/// Exact amount of money. Amounts travel as strings ("12.50") so no precision
+/// is lost on the way to the backend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub struct Money {
+    #[serde(with = "rust_decimal::serde::str")]
+    pub amount: Decimal,
+    pub currency: Currency,
+}
+
+impl Money {
+    pub fn new(amount: Decimal, currency: Currency) -> Money {
+        Money { amount, currency }
+    }
+
+    /// Percentage `self` is of `whole`, always with two decimals ("15.00")
+    pub fn percent_of(&self, whole: &Money) -> Decimal {
+        let mut percent = match whole.amount.is_zero() {
+            true => Decimal::ZERO,
+            false => (self.amount / whole.amount * Decimal::ONE_HUNDRED).round_dp(2),
+        };
+        percent.rescale(2);
+        percent
+    }
+}
+
+impl std::fmt::Display for Money {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:.2} {}", self.amount, self.currency)
+    }
+}
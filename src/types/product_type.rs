@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+
+/// What kind of product an entry is, see the name rules with a `product_type`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProductType {
+    /// Unless a name rule tells otherwise
+    #[default]
+    BaseGame,
+    Expansion,
+    Promo,
+    /// Not released yet, "preventa"
+    Preorder,
+    /// Several games or game plus extras
+    Bundle,
+    /// Sleeves, inserts, playmats, dice...
+    Accessory,
+}
+
+impl ProductType {
+
+    pub const ALL: [ProductType; 6] = [
+        ProductType::BaseGame,
+        ProductType::Expansion,
+        ProductType::Promo,
+        ProductType::Preorder,
+        ProductType::Bundle,
+        ProductType::Accessory,
+    ];
+
+    /// As written in `publish_types` and the name rules
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProductType::BaseGame => "base_game",
+            ProductType::Expansion => "expansion",
+            ProductType::Promo => "promo",
+            ProductType::Preorder => "preorder",
+            ProductType::Bundle => "bundle",
+            ProductType::Accessory => "accessory",
+        }
+    }
+}
+
+impl std::fmt::Display for ProductType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
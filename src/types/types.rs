@@ -1,26 +1,128 @@
 // Given an URL, a page is formed with information about next URL and a list of page_entries to
 // scrap
 
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use crate::telemetry::SpannedMessage;
+use crate::types::{Availability, Condition, Currency, Money, ProductType};
+
+/// Bumped whenever `Offer` changes in a way consumers should know about
+pub const OFFER_SCHEMA_VERSION: u32 = 6;
+
+fn first_schema_version() -> u32 {
+    1
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Offer {
+    /// Offers from before the field existed are version 1
+    #[serde(default = "first_schema_version")]
+    pub schema_version: u32,
     pub url: String,
     pub name: String,
-    pub normal_price: f64,
-    pub offer_price: f64,
-    pub availability: String,
+    pub normal_price: Money,
+    pub offer_price: Money,
+    /// Currency of both prices
+    #[serde(default)]
+    pub currency: Currency,
+    /// Percentage off `normal_price`, two decimals
+    #[serde(default, with = "rust_decimal::serde::str")]
+    pub discount_percent: Decimal,
+    /// `normal_price` minus `offer_price`
+    #[serde(default)]
+    pub savings: Money,
+    /// Stock status, see `rules::AvailabilityMap`
+    #[serde(default)]
+    pub availability: Availability,
+    /// Availability as the shop tells it
+    #[serde(default)]
+    pub raw_availability: String,
     pub shop_name: String,
+    /// Name as found in the shop, before the name rules
+    #[serde(default)]
+    pub raw_name: String,
+    /// Id of every name rule that rewrote `raw_name` into `name`
+    #[serde(default)]
+    pub name_rules: Vec<String>,
+    /// Language of the edition ("es", "en"...) when the name tells, kept apart
+    /// from `name` so editions are not mixed up
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Second-hand or damaged items, when the name tells
+    #[serde(default)]
+    pub condition: Condition,
+    /// Base game unless a name rule says otherwise
+    #[serde(default)]
+    pub product_type: ProductType,
+    /// Hour the listing was scraped, "%Y-%m-%d_%H", shared by the whole page
+    #[serde(default)]
+    pub batch: String,
+    /// Only filled in runs with `--details`
+    #[serde(flatten)]
+    pub details: ProductDetails,
+    /// Run that scraped the offer, see `parser::ParserContext`
+    #[serde(default)]
+    pub run_id: String,
+    #[serde(default)]
+    pub scraped_at: DateTime<Utc>,
 }
 
-#[derive(Debug)]
-pub struct PageEntry {
+/// An entry that was dropped on purpose by a name rule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rejection {
     pub url: String,
-    pub name: String
+    pub raw_name: String,
+    pub shop_name: String,
+    /// Id of the rule that fired
+    pub rule: String,
+}
+
+/// What the product page tells beyond the listing. Much better than the name
+/// to match a product with a game.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProductDetails {
+    /// EAN or any other GTIN, digits only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ean: Option<String>,
+    /// Reference of the shop
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sku: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub publisher: Option<String>,
+    /// As the shop writes them: "2-4", "30-60 min", "10+"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub players: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub playtime: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub age_rating: Option<String>,
+    /// Absolute URL of the main picture
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_url: Option<String>,
+}
+
+/// Everything a parser reports while scraping
+// Offers are the common case, boxing them would just move the size around
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone)]
+pub enum ScrapeEvent {
+    Offer(SpannedMessage<Offer>),
+    Rejected(Rejection),
+}
+
+// Raw values as found in the listing, nothing is validated yet
+#[derive(Debug, Clone, Default)]
+pub struct PageEntry {
+    pub url: Option<String>,
+    pub name: Option<String>,
+    pub offer_price: Option<String>,
+    pub normal_price: Option<String>,
+    pub availability: Option<String>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct Page {
-    pub next_url: String,
+    pub next_url: Option<String>,
     pub entries: Vec<PageEntry>
 }
@@ -0,0 +1,39 @@
+// The receiving side relies on these, keep them passing
+use chrono::{TimeZone, Utc};
+use aragog::auth::{sign, verify, BackendAuth, VerifyError, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+
+const KEY: &[u8] = b"not so secret";
+const BODY: &[u8] = br#"{"body":{"name":"Catan"}}"#;
+
+#[test]
+fn signed_headers_verify() {
+    let auth = BackendAuth::new(Some(String::from("token")), Some(KEY.to_vec()));
+    let headers = auth.headers(BODY);
+    let header = |name: &str| headers.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str()).unwrap();
+
+    assert_eq!(header("Authorization"), "Bearer token");
+    assert_eq!(verify(KEY, header(TIMESTAMP_HEADER), header(SIGNATURE_HEADER), BODY, Utc::now(), 300), Ok(()));
+}
+
+#[test]
+fn tampered_body_does_not_verify() {
+    let signature = format!("sha256={}", sign(KEY, 1_700_000_000, BODY));
+    let now = Utc.timestamp_opt(1_700_000_010, 0).unwrap();
+
+    assert_eq!(verify(KEY, "1700000000", &signature, BODY, now, 300), Ok(()));
+    assert_eq!(verify(KEY, "1700000000", &signature, br#"{"body":{"name":"Catan 2"}}"#, now, 300), Err(VerifyError::Mismatch));
+    assert_eq!(verify(b"other key", "1700000000", &signature, BODY, now, 300), Err(VerifyError::Mismatch));
+    assert_eq!(verify(KEY, "1700000000", "deadbeef", BODY, now, 300), Err(VerifyError::BadSignature));
+}
+
+#[test]
+fn old_requests_do_not_verify() {
+    let signature = format!("sha256={}", sign(KEY, 1_700_000_000, BODY));
+    let later = Utc.timestamp_opt(1_700_000_000 + 3600, 0).unwrap();
+
+    assert_eq!(verify(KEY, "1700000000", &signature, BODY, later, 300), Err(VerifyError::Stale(3600)));
+    assert_eq!(verify(KEY, "yesterday", &signature, BODY, later, 300), Err(VerifyError::BadTimestamp));
+    // Too far away to even tell how far
+    assert_eq!(verify(KEY, "-9223372036854775808", &signature, BODY, later, 300), Err(VerifyError::BadTimestamp));
+    assert!(matches!(verify(KEY, "9223372036854775807", &signature, BODY, later, 300), Err(VerifyError::Stale(_))));
+}
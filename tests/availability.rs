@@ -0,0 +1,42 @@
+// Mapping of availability texts, as in configuration.yaml
+use aragog::configuration::{get_configuration, AvailabilitySettings};
+use aragog::rules::AvailabilityMap;
+use aragog::types::Availability;
+
+fn global() -> Vec<AvailabilitySettings> {
+    get_configuration().expect("Failed to read configuration file").availability
+}
+
+#[test]
+fn longest_text_wins() {
+    let map = AvailabilityMap::compile(&global(), &[]);
+
+    assert_eq!(map.classify("  EN   STOCK "), Availability::InStock);
+    assert_eq!(map.classify("No disponible"), Availability::OutOfStock);
+    assert_eq!(map.classify("Disponible bajo pedido (7 días)"), Availability::OnDemand);
+    assert_eq!(map.classify("Últimas unidades en stock"), Availability::LowStock);
+}
+
+#[test]
+fn shop_texts_go_first() {
+    let shop = vec![AvailabilitySettings { status: Availability::Preorder, values: vec![String::from("Disponible")] }];
+    let map = AvailabilityMap::compile(&global(), &shop);
+
+    assert_eq!(map.classify("Disponible"), Availability::Preorder);
+    assert_eq!(map.classify("Agotado"), Availability::OutOfStock);
+}
+
+#[test]
+fn unknown_texts_are_unknown() {
+    let map = AvailabilityMap::compile(&global(), &[]);
+
+    assert_eq!(map.classify(""), Availability::Unknown);
+    assert_eq!(map.classify("Llega el jueves"), Availability::Unknown);
+}
+
+#[test]
+fn names_are_the_serde_ones() {
+    for availability in [Availability::InStock, Availability::LowStock, Availability::OutOfStock, Availability::Preorder, Availability::OnDemand, Availability::Unknown] {
+        assert_eq!(serde_json::to_value(availability).unwrap(), availability.as_str());
+    }
+}
@@ -0,0 +1,111 @@
+// Batches against a backend that answers whatever each test wants
+mod common;
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use async_trait::async_trait;
+use futures::future::join_all;
+use serde_json::Value;
+use tokio::sync::Semaphore;
+use aragog::auth::BackendAuth;
+use aragog::http::{Fetch, FetchedPage};
+use aragog::parser::ScrapeError;
+use aragog::sink::{BackendSink, BulkBackendSink, Delivery, OfferSink};
+use common::offer;
+
+const BULK_URL: &str = "http://backend/offers/bulk";
+const SINGLE_URL: &str = "http://backend/offers";
+
+// Answers posts to the bulk endpoint with `bulk`, and single posts with 200.
+// Keeps every URL and body it got.
+struct Backend {
+    bulk: FetchedPage,
+    posts: Mutex<Vec<(String, Value)>>,
+}
+
+#[async_trait]
+impl Fetch for Backend {
+    async fn fetch(&self, url: &str) -> Result<FetchedPage, ScrapeError> {
+        Err(ScrapeError::Http(format!("Nothing to fetch from {}", url)))
+    }
+
+    async fn post_json(&self, url: &str, body: &Value, _: &[(String, String)]) -> Result<FetchedPage, ScrapeError> {
+        self.posts.lock().unwrap().push((url.to_string(), body.clone()));
+        match url {
+            BULK_URL => Ok(self.bulk.clone()),
+            _ => Ok(FetchedPage { status: 200, body: String::new() }),
+        }
+    }
+}
+
+fn sink(status: u16, body: &str, batch_size: usize, max_wait: Duration) -> (BulkBackendSink, Arc<Backend>) {
+    let backend = Arc::new(Backend {
+        bulk: FetchedPage { status, body: body.to_string() },
+        posts: Mutex::new(vec![]),
+    });
+    let single = BackendSink::new("http://backend", "offers", backend.clone(), Arc::new(Semaphore::new(4)), BackendAuth::default());
+    (BulkBackendSink::new(single, "offers/bulk", batch_size, max_wait), backend)
+}
+
+// Publishes every name at once, so they all fit in the same batch
+async fn publish(sink: &BulkBackendSink, names: &[&str]) -> Vec<Option<Delivery>> {
+    let offers: Vec<_> = names.iter().map(|name| offer("Dracotienda", name, 40, 30)).collect();
+    join_all(offers.iter().map(|offer| sink.publish(offer))).await
+        .into_iter()
+        .map(Result::ok)
+        .collect()
+}
+
+fn urls(backend: &Backend) -> Vec<String> {
+    backend.posts.lock().unwrap().iter().map(|(url, _)| url.clone()).collect()
+}
+
+#[tokio::test]
+async fn every_offer_gets_its_own_status() {
+    let (sink, backend) = sink(200, "[200, 515, 500]", 3, Duration::from_secs(60));
+
+    let deliveries = publish(&sink, &["Catan", "Azul", "Wingspan"]).await;
+
+    assert_eq!(deliveries, vec![Some(Delivery::Accepted), Some(Delivery::Unmatched), None]);
+    let posts = backend.posts.lock().unwrap();
+    assert_eq!(posts.len(), 1);
+    let names: Vec<&str> = posts[0].1.as_array().unwrap().iter().map(|offer| offer["body"]["name"].as_str().unwrap()).collect();
+    assert_eq!(names, vec!["Catan", "Azul", "Wingspan"]);
+}
+
+#[tokio::test]
+async fn bodies_without_statuses_mean_all_taken() {
+    let (sink, _) = sink(200, "", 2, Duration::from_secs(60));
+
+    assert_eq!(publish(&sink, &["Catan", "Azul"]).await, vec![Some(Delivery::Accepted); 2]);
+}
+
+#[tokio::test]
+async fn statuses_for_other_offers_fail_the_batch() {
+    let (sink, backend) = sink(200, "[200]", 2, Duration::from_secs(60));
+
+    assert_eq!(publish(&sink, &["Catan", "Azul"]).await, vec![None, None]);
+    assert_eq!(urls(&backend), vec![BULK_URL]);
+}
+
+#[tokio::test]
+async fn backends_without_bulk_get_single_posts() {
+    for status in [404, 405, 501] {
+        let (sink, backend) = sink(status, "", 2, Duration::from_secs(60));
+
+        assert_eq!(publish(&sink, &["Catan", "Azul"]).await, vec![Some(Delivery::Accepted); 2]);
+        // Once is enough to know
+        assert_eq!(publish(&sink, &["Wingspan", "Terraforming Mars"]).await, vec![Some(Delivery::Accepted); 2]);
+        assert_eq!(urls(&backend), vec![BULK_URL, SINGLE_URL, SINGLE_URL, SINGLE_URL, SINGLE_URL], "{}", status);
+    }
+}
+
+#[tokio::test]
+async fn short_batches_go_after_max_wait() {
+    let (sink, backend) = sink(200, "[200]", 10, Duration::from_millis(50));
+
+    let delivery = tokio::time::timeout(Duration::from_secs(5), sink.publish(&offer("Dracotienda", "Catan", 40, 30))).await;
+
+    assert_eq!(delivery.expect("Batch never sent").unwrap(), Delivery::Accepted);
+    assert_eq!(urls(&backend), vec![BULK_URL]);
+}
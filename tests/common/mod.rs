@@ -0,0 +1,60 @@
+// Helpers shared by the integration tests, each test file uses only some
+#![allow(dead_code)]
+
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use rust_decimal::Decimal;
+use aragog::telemetry::{PropagationContext, SpannedMessage};
+use aragog::types::{Availability, Condition, Currency, Money, Offer, ProductType, OFFER_SCHEMA_VERSION};
+
+/// An offer of `shop_name` with no trace context, prices in euros
+pub fn offer(shop_name: &str, name: &str, normal_price: i64, offer_price: i64) -> SpannedMessage<Offer> {
+    SpannedMessage::new(PropagationContext::inject(&opentelemetry::Context::new()), Offer {
+        url: format!("https://example.com/{}", name),
+        name: name.to_string(),
+        normal_price: Money::new(Decimal::from(normal_price), Currency::Eur),
+        offer_price: Money::new(Decimal::from(offer_price), Currency::Eur),
+        currency: Currency::Eur,
+        discount_percent: Decimal::from(normal_price - offer_price) * Decimal::ONE_HUNDRED / Decimal::from(normal_price),
+        savings: Money::new(Decimal::from(normal_price - offer_price), Currency::Eur),
+        availability: Availability::InStock,
+        raw_availability: String::from("En stock"),
+        shop_name: shop_name.to_string(),
+        raw_name: name.to_string(),
+        name_rules: vec![],
+        language: None,
+        condition: Condition::New,
+        product_type: ProductType::BaseGame,
+        details: Default::default(),
+        batch: String::new(),
+        run_id: String::from("test"),
+        scraped_at: chrono::Utc::now(),
+        schema_version: OFFER_SCHEMA_VERSION,
+    })
+}
+
+/// Empty directory of a single test in `$TMPDIR`, removed once dropped
+pub struct Scratch(PathBuf);
+
+impl Scratch {
+    pub fn new(name: &str) -> Scratch {
+        let dir = std::env::temp_dir().join(format!("aragog-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        Scratch(dir)
+    }
+}
+
+impl Deref for Scratch {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
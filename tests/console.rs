@@ -0,0 +1,55 @@
+// What --dry-run prints
+mod common;
+
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use aragog::sink::{ConsoleSink, OfferSink};
+use aragog::telemetry::{PropagationContext, SpannedMessage};
+use aragog::types::Rejection;
+use common::offer;
+
+// Keeps whatever is printed, for the test to read
+#[derive(Clone, Default)]
+struct Screen(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Screen {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Screen {
+    fn lines(&self) -> Vec<String> {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap().lines().map(String::from).collect()
+    }
+}
+
+#[tokio::test]
+async fn offers_and_rejections_are_a_line_each() {
+    let screen = Screen::default();
+    let sink = ConsoleSink::new(Box::new(screen.clone()));
+
+    let mut renamed = offer("Dracotienda", "Azul", 40, 30).unwrap();
+    renamed.raw_name = String::from("Azul (ES)");
+    renamed.language = Some(String::from("es"));
+    renamed.name_rules = vec![String::from("language")];
+    let renamed = SpannedMessage::new(PropagationContext::inject(&opentelemetry::Context::new()), renamed);
+    sink.publish(&offer("Dracotienda", "Catan", 40, 30)).await.unwrap();
+    sink.publish(&renamed).await.unwrap();
+    sink.reject(&Rejection {
+        url: String::from("https://example.com/fundas"),
+        raw_name: String::from("Fundas Catan"),
+        shop_name: String::from("Dracotienda"),
+        rule: String::from("accessory"),
+    }).await.unwrap();
+
+    assert_eq!(screen.lines(), vec![
+        "OFFER    Dracotienda | Catan | base_game, ?, new | 30.00 EUR (normal 40.00 EUR) | in_stock (En stock) | https://example.com/Catan",
+        "OFFER    Dracotienda | Azul (was `Azul (ES)`, rules: language) | base_game, es, new | 30.00 EUR (normal 40.00 EUR) | in_stock (En stock) | https://example.com/Azul",
+        "REJECTED Dracotienda | Fundas Catan | rule: accessory | https://example.com/fundas",
+    ]);
+}
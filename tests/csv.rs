@@ -0,0 +1,32 @@
+// CSV files as spreadsheets read them
+mod common;
+
+use chrono::{TimeZone, Utc};
+use rust_decimal::Decimal;
+use aragog::sink::{CsvSink, OfferSink};
+use aragog::telemetry::{PropagationContext, SpannedMessage};
+use aragog::types::{Condition, Money};
+use common::{offer, Scratch};
+
+#[tokio::test]
+async fn rows_keep_their_columns() {
+    let dir = Scratch::new("csv");
+    let path = dir.join("offers.csv");
+    let mut catan = offer("Dracotienda", "Catan, \"Edición\"", 1300, 1000).unwrap();
+    catan.normal_price = Money::new(Decimal::new(123450, 2), catan.currency);
+    catan.offer_price = Money::new(Decimal::new(1000, 0), catan.currency);
+    catan.savings = Money::new(Decimal::new(2345, 1), catan.currency);
+    catan.discount_percent = Decimal::new(18995, 3);
+    catan.language = Some(String::from("es"));
+    catan.condition = Condition::DamagedBox;
+    catan.scraped_at = Utc.with_ymd_and_hms(2024, 3, 1, 10, 30, 0).unwrap();
+
+    let sink = CsvSink::new(&path).unwrap();
+    sink.publish(&SpannedMessage::new(PropagationContext::inject(&opentelemetry::Context::new()), catan)).await.unwrap();
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(contents.lines().collect::<Vec<_>>(), vec![
+        "shop,name,url,normal_price,offer_price,discount,availability,scraped_at,currency,savings,run_id,status,language,condition,product_type",
+        "Dracotienda,\"Catan, \"\"Edición\"\"\",\"https://example.com/Catan, \"\"Edición\"\"\",1234.50,1000.00,19.00,En stock,2024-03-01T10:30:00Z,EUR,234.50,test,in_stock,es,damaged_box,base_game",
+    ]);
+}
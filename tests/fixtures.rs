@@ -0,0 +1,132 @@
+/* Runs every shop parser against the HTML saved in `tests/fixtures/<shop id>`
+ * and compares what comes out with the `snapshot.json` next to it.
+ *
+ * Each shop directory holds:
+ *  - `pages.json`: URL -> file in `pages/`, for every page the parser asks for
+ *  - `pages/`: the saved listing and product pages
+ *  - `snapshot.json`: stats and events the parser is expected to produce
+ *
+ * Run with `UPDATE_SNAPSHOTS=1` to rewrite the snapshots after a deliberate
+ * change, and review the diff.
+ */
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use tokio::sync::Semaphore;
+use aragog::configuration::get_configuration;
+use aragog::http::{Fetch, FetchedPage};
+use aragog::parser::{ParserContext, ScrapeError, ShopRegistry};
+use aragog::types::ScrapeEvent;
+
+struct FixtureFetch {
+    dir: PathBuf,
+    pages: HashMap<String, String>,
+}
+
+impl FixtureFetch {
+    fn new(dir: PathBuf) -> FixtureFetch {
+        let index = std::fs::read_to_string(dir.join("pages.json")).expect("Missing pages.json");
+        let pages = serde_json::from_str(&index).expect("Bad pages.json");
+        FixtureFetch { dir, pages }
+    }
+}
+
+#[async_trait]
+impl Fetch for FixtureFetch {
+    async fn fetch(&self, url: &str) -> Result<FetchedPage, ScrapeError> {
+        // Anything that was not saved is just not there
+        let file = match self.pages.get(url) {
+            Some(file) => file,
+            None => return Ok(FetchedPage { status: 404, body: String::new() }),
+        };
+        let body = std::fs::read_to_string(self.dir.join("pages").join(file)).expect("Missing fixture page");
+        Ok(FetchedPage { status: 200, body })
+    }
+
+    async fn post_json(&self, url: &str, _: &Value, _: &[(String, String)]) -> Result<FetchedPage, ScrapeError> {
+        Err(ScrapeError::Http(format!("Fixtures do not post, yet something posted to {}", url)))
+    }
+}
+
+async fn scrape_fixture(shop_id: &str) -> Value {
+    let configuration = get_configuration().expect("Failed to read configuration file");
+    let registry = ShopRegistry::new(&configuration.shops).unwrap();
+    let mut shop = registry.get(shop_id).expect("Shop not in configuration.yaml").clone();
+
+    // No reason to be polite with files
+    shop.settings.retry_delay_secs = 0;
+    shop.settings.details.delay_secs = 0;
+    if let Some(truncated) = shop.settings.truncated_name.as_mut() {
+        truncated.detail_delay_secs = 0;
+    }
+
+    let context = ParserContext {
+        name_rules: configuration.name_rules.clone(),
+        availability: configuration.availability.clone(),
+        publish_types: configuration.publish_types.clone(),
+        global_limit: Arc::new(Semaphore::new(1)),
+        run_id: String::from("fixture"),
+        details: true,
+    };
+    let parser = shop.build(&context).unwrap();
+    let fetch = FixtureFetch::new(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(shop_id));
+
+    let (events, mut received) = tokio::sync::mpsc::channel(1024);
+    let mut stats = aragog::parser::ScrapeStats::default();
+    for url in &shop.settings.start_urls {
+        stats.merge(&parser.process(&fetch, url, 1000, &events).await.unwrap());
+    }
+    drop(events);
+
+    let mut produced = vec![];
+    while let Some(event) = received.recv().await {
+        produced.push(match event {
+            ScrapeEvent::Offer(offer) => {
+                // The batch and timestamp depend on the clock, not something to snapshot
+                let mut offer = offer.unwrap();
+                offer.batch.clear();
+                offer.scraped_at = Default::default();
+                json!({ "offer": offer })
+            }
+            ScrapeEvent::Rejected(rejection) => json!({ "rejected": rejection }),
+        });
+    }
+
+    json!({ "stats": stats, "events": produced })
+}
+
+async fn check_snapshot(shop_id: &str) {
+    let actual = scrape_fixture(shop_id).await;
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(shop_id).join("snapshot.json");
+
+    if std::env::var("UPDATE_SNAPSHOTS").is_ok() || !path.exists() {
+        let existed = path.exists();
+        std::fs::write(&path, serde_json::to_string_pretty(&actual).unwrap() + "\n").unwrap();
+        assert!(existed, "Created {}, review it and run the tests again", path.display());
+        return;
+    }
+
+    let expected: Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(
+        actual, expected,
+        "{} no longer matches its snapshot, run with UPDATE_SNAPSHOTS=1 if that is on purpose",
+        shop_id
+    );
+}
+
+#[tokio::test]
+async fn dracotienda_matches_snapshot() {
+    check_snapshot("dracotienda").await;
+}
+
+#[tokio::test]
+async fn jugamosotra_matches_snapshot() {
+    check_snapshot("jugamosotra").await;
+}
+
+#[tokio::test]
+async fn dungeonmarvels_matches_snapshot() {
+    check_snapshot("dungeonmarvels").await;
+}
@@ -0,0 +1,29 @@
+// JSON Lines files as jq reads them
+mod common;
+
+use serde_json::Value;
+use aragog::sink::{JsonLinesSink, OfferSink};
+use common::{offer, Scratch};
+
+#[tokio::test]
+async fn every_offer_is_a_line_of_its_own() {
+    let dir = Scratch::new("json-lines");
+    let path = dir.join("offers.jsonl");
+
+    let sink = JsonLinesSink::new(&path).unwrap();
+    sink.publish(&offer("Dracotienda", "Catan", 40, 30)).await.unwrap();
+    sink.publish(&offer("Dracotienda", "Azul\nEdición", 40, 30)).await.unwrap();
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert!(contents.ends_with('\n'));
+    let lines: Vec<Value> = contents.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["name"], "Catan");
+    // Line breaks in names are escaped, never written as such
+    assert_eq!(lines[1]["name"], "Azul\nEdición");
+    for line in &lines {
+        assert_eq!(line["run_id"], "test");
+        assert!(line["scraped_at"].is_string());
+        assert!(line.get("context").is_none());
+    }
+}
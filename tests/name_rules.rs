@@ -0,0 +1,129 @@
+// Name rules of configuration.yaml against names seen in the shops
+use aragog::configuration::{get_configuration, NameRuleSettings, RuleAction};
+use aragog::rules::{NameRules, NameTags, NameVerdict};
+use aragog::types::{Condition, ProductType};
+
+fn rules() -> NameRules {
+    let configuration = get_configuration().expect("Failed to read configuration file");
+    NameRules::compile(&configuration.name_rules, &[]).unwrap()
+}
+
+fn accepted(raw_name: &str) -> (String, NameTags) {
+    match rules().apply(raw_name) {
+        NameVerdict::Accepted { name, tags, .. } => (name, tags),
+        NameVerdict::Rejected { rule } => panic!("{} rejected by {}", raw_name, rule),
+    }
+}
+
+#[test]
+fn markers_become_tags() {
+    let (name, tags) = accepted("Azul (Inglés) (caja dañada)");
+
+    assert_eq!(name, "Azul");
+    assert_eq!(tags.language.as_deref(), Some("en"));
+    assert_eq!(tags.condition, Some(Condition::DamagedBox));
+    assert_eq!(tags.product_type, None);
+}
+
+#[test]
+fn product_types_keep_the_name() {
+    let (name, tags) = accepted("Catan: Expansión Navegantes");
+    assert_eq!(name, "Catan: Expansión Navegantes");
+    assert_eq!(tags.product_type, Some(ProductType::Expansion));
+    assert_eq!(tags.product_type_rule.as_deref(), Some("expansion"));
+
+    let (name, tags) = accepted("PREVENTA: Frosthaven (castellano)");
+    assert_eq!(name, "Frosthaven");
+    assert_eq!(tags.product_type, Some(ProductType::Preorder));
+    assert_eq!(tags.language.as_deref(), Some("es"));
+}
+
+#[test]
+fn plain_names_are_untouched() {
+    let (name, tags) = accepted("Expansive Wingspan Sleeveless");
+
+    assert_eq!(name, "Expansive Wingspan Sleeveless");
+    assert_eq!(tags, NameTags::default());
+}
+
+fn rule(id: &str, action: RuleAction, pattern: &str) -> NameRuleSettings {
+    NameRuleSettings {
+        id: id.to_string(),
+        action,
+        pattern: pattern.to_string(),
+        replacement: String::new(),
+        language: None,
+        condition: None,
+        product_type: None,
+        enabled: true,
+    }
+}
+
+fn fired(rules: &NameRules, raw_name: &str) -> Vec<String> {
+    match rules.apply(raw_name) {
+        NameVerdict::Accepted { fired, .. } => fired,
+        NameVerdict::Rejected { rule } => vec![format!("rejected by {}", rule)],
+    }
+}
+
+#[test]
+fn shop_rules_run_first() {
+    let global = vec![rule("strip_promo", RuleAction::Strip, "(?i)promo"), rule("tag", RuleAction::Tag, "Catan")];
+    let mut shop = vec![rule("shop_replace", RuleAction::Replace, "Katan")];
+    shop[0].replacement = String::from("Catan");
+    let rules = NameRules::compile(&global, &shop).unwrap();
+
+    // The shop rule fixes the name before the global tag sees it
+    assert_eq!(fired(&rules, "Katan promo"), vec!["shop_replace", "strip_promo", "tag"]);
+}
+
+#[test]
+fn shop_rules_replace_global_ones_in_place() {
+    let global = vec![
+        rule("first", RuleAction::Tag, "Catan"),
+        rule("promo", RuleAction::Reject, "(?i)promo"),
+        rule("last", RuleAction::Tag, "Catan"),
+    ];
+    let shop = vec![rule("promo", RuleAction::Strip, "(?i)promo")];
+    let rules = NameRules::compile(&global, &shop).unwrap();
+
+    assert_eq!(fired(&rules, "Catan promo"), vec!["first", "promo", "last"]);
+    match rules.apply("Catan promo") {
+        NameVerdict::Accepted { name, .. } => assert_eq!(name, "Catan"),
+        NameVerdict::Rejected { rule } => panic!("Rejected by {}", rule),
+    }
+}
+
+#[test]
+fn disabled_rules_do_not_run() {
+    let global = vec![rule("promo", RuleAction::Reject, "(?i)promo"), rule("tag", RuleAction::Tag, "Catan")];
+    let mut disabled = rule("promo", RuleAction::Reject, "(?i)promo");
+    disabled.enabled = false;
+
+    let rules = NameRules::compile(&global, &[]).unwrap();
+    assert_eq!(fired(&rules, "Catan promo"), vec!["rejected by promo"]);
+
+    let rules = NameRules::compile(&global, &[disabled.clone()]).unwrap();
+    assert_eq!(fired(&rules, "Catan promo"), vec!["tag"]);
+
+    // Disabled shop rules of their own just do nothing
+    disabled.id = String::from("shop_promo");
+    let rules = NameRules::compile(&[], &[disabled]).unwrap();
+    assert_eq!(fired(&rules, "Catan promo"), Vec::<String>::new());
+}
+
+#[test]
+fn bad_patterns_name_their_rule() {
+    let error = NameRules::compile(&[rule("broken", RuleAction::Strip, "(")], &[]).unwrap_err();
+    assert!(error.to_string().contains("`broken`"));
+}
+
+#[test]
+fn tags_are_named_as_serde_names_them() {
+    for product_type in ProductType::ALL {
+        assert_eq!(serde_json::to_value(product_type).unwrap(), product_type.as_str());
+    }
+    for condition in [Condition::New, Condition::LikeNew, Condition::DamagedBox] {
+        assert_eq!(serde_json::to_value(condition).unwrap(), condition.as_str());
+    }
+}
@@ -0,0 +1,149 @@
+// Outbox in a scratch directory, drained through a sink that fails on demand
+mod common;
+
+use std::path::Path;
+use std::sync::Arc;
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use color_eyre::Report;
+use aragog::outbox::{DrainReport, Outbox, OutboxEntry};
+use aragog::parser::ScrapeError;
+use aragog::sink::{Delivery, OfferSink, OutboxSink};
+use aragog::telemetry::SpannedMessage;
+use aragog::types::Offer;
+use common::{offer, Scratch};
+
+// Fails offers by name: "Azul" for a while (503), "Wingspan" for good (400)
+struct Backend;
+
+#[async_trait]
+impl OfferSink for Backend {
+    async fn publish(&self, offer: &SpannedMessage<Offer>) -> Result<Delivery, Report> {
+        match offer.body().name.as_str() {
+            "Azul" => Err(ScrapeError::BackendRejected(503).into()),
+            "Wingspan" => Err(ScrapeError::BackendRejected(400).into()),
+            _ => Ok(Delivery::Accepted),
+        }
+    }
+}
+
+fn files(dir: &Path) -> Vec<String> {
+    let mut files: Vec<String> = std::fs::read_dir(dir).unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    files.sort();
+    files
+}
+
+fn dead_letters(dir: &Path) -> Vec<serde_json::Value> {
+    std::fs::read_to_string(dir.join("dead-letter.jsonl")).unwrap_or_default()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[test]
+fn pushed_offers_wait_in_order() {
+    let dir = Scratch::new("outbox-push");
+    let outbox = Outbox::open(&dir, Duration::days(7)).unwrap();
+
+    outbox.push(&offer("Dracotienda", "Catan", 40, 30), "backend answered 503").unwrap();
+    outbox.push(&offer("Dracotienda", "Azul", 40, 30), "request failed: timeout").unwrap();
+
+    let entries = outbox.entries().unwrap();
+    let names: Vec<&str> = entries.iter().map(|(_, entry)| entry.message.body().name.as_str()).collect();
+    assert_eq!(names, vec!["Catan", "Azul"]);
+    assert_eq!(entries[0].1.attempts, 1);
+    assert_eq!(entries[0].1.last_error, "backend answered 503");
+    // Nothing half written is left behind
+    assert!(files(&dir).iter().all(|file| file.ends_with(".json")));
+}
+
+#[tokio::test]
+async fn only_transient_failures_are_kept() {
+    let dir = Scratch::new("outbox-sink");
+    let outbox = Arc::new(Outbox::open(&dir, Duration::days(7)).unwrap());
+    let sink = OutboxSink::new(Box::new(Backend), outbox.clone());
+
+    assert!(sink.publish(&offer("Dracotienda", "Catan", 40, 30)).await.is_ok());
+    assert!(sink.publish(&offer("Dracotienda", "Azul", 40, 30)).await.is_err());
+    assert!(sink.publish(&offer("Dracotienda", "Wingspan", 40, 30)).await.is_err());
+
+    let entries = outbox.entries().unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].1.message.body().name, "Azul");
+}
+
+#[tokio::test]
+async fn draining_delivers_and_buries() {
+    let dir = Scratch::new("outbox-drain");
+    let outbox = Outbox::open(&dir, Duration::days(7)).unwrap();
+    for name in ["Catan", "Wingspan", "Terraforming Mars"] {
+        outbox.push(&offer("Dracotienda", name, 40, 30), "backend answered 503").unwrap();
+    }
+
+    let report = outbox.drain(&Backend).await.unwrap();
+
+    assert_eq!(report, DrainReport { delivered: 2, pending: 0, dead: 1 });
+    assert!(outbox.entries().unwrap().is_empty());
+    let dead = dead_letters(&dir);
+    assert_eq!(dead.len(), 1);
+    assert_eq!(dead[0]["reason"], "rejected");
+    assert_eq!(dead[0]["message"]["body"]["name"], "Wingspan");
+    assert_eq!(dead[0]["last_error"], "backend answered 400");
+    assert!(files(&dir).iter().all(|file| !file.ends_with(".tmp")));
+}
+
+#[tokio::test]
+async fn draining_stops_while_the_backend_is_down() {
+    let dir = Scratch::new("outbox-down");
+    let outbox = Outbox::open(&dir, Duration::days(7)).unwrap();
+    for name in ["Catan", "Azul", "Wingspan"] {
+        outbox.push(&offer("Dracotienda", name, 40, 30), "backend answered 503").unwrap();
+    }
+
+    let report = outbox.drain(&Backend).await.unwrap();
+
+    assert_eq!(report, DrainReport { delivered: 1, pending: 2, dead: 0 });
+    let entries = outbox.entries().unwrap();
+    let attempts: Vec<(&str, usize)> = entries.iter().map(|(_, entry)| (entry.message.body().name.as_str(), entry.attempts)).collect();
+    // Wingspan was not even tried
+    assert_eq!(attempts, vec![("Azul", 2), ("Wingspan", 1)]);
+    assert!(dead_letters(&dir).is_empty());
+}
+
+#[tokio::test]
+async fn old_entries_expire_untried() {
+    let dir = Scratch::new("outbox-expire");
+    let outbox = Outbox::open(&dir, Duration::days(7)).unwrap();
+    let old = Utc::now() - Duration::days(8);
+    let entry = OutboxEntry {
+        first_failed_at: old,
+        last_attempt_at: old,
+        attempts: 3,
+        last_error: String::from("backend answered 503"),
+        message: offer("Dracotienda", "Catan", 40, 30),
+    };
+    std::fs::write(dir.join("old.json"), serde_json::to_vec(&entry).unwrap()).unwrap();
+
+    let report = outbox.drain(&Backend).await.unwrap();
+
+    assert_eq!(report, DrainReport { delivered: 0, pending: 0, dead: 1 });
+    let dead = dead_letters(&dir);
+    assert_eq!(dead[0]["reason"], "expired");
+    assert_eq!(dead[0]["attempts"], 3);
+    assert_eq!(files(&dir), vec!["dead-letter.jsonl"]);
+}
+
+#[test]
+fn half_written_entries_are_ignored() {
+    let dir = Scratch::new("outbox-partial");
+    let outbox = Outbox::open(&dir, Duration::days(7)).unwrap();
+    outbox.push(&offer("Dracotienda", "Catan", 40, 30), "backend answered 503").unwrap();
+    // What a crash in the middle of a write leaves behind
+    std::fs::write(dir.join("crashed.tmp"), "{\"first_failed_at\":").unwrap();
+
+    let entries = outbox.entries().unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].1.message.body().name, "Catan");
+}
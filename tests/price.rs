@@ -0,0 +1,62 @@
+// Price tags as shops write them
+use aragog::parser::{normalize_amount, parse_price, PriceError};
+use aragog::types::Currency;
+
+#[test]
+fn prices_are_parsed() {
+    let cases = [
+        ("1.234,56 €", Currency::Usd, "1234.56", Currency::Eur),
+        ("1,234.56 $", Currency::Eur, "1234.56", Currency::Usd),
+        ("Desde 12,00 €", Currency::Eur, "12.00", Currency::Eur),
+        ("12,00\u{a0}€", Currency::Eur, "12.00", Currency::Eur),
+        ("1\u{202f}234,56 €", Currency::Eur, "1234.56", Currency::Eur),
+        ("12,00 € 10,00 €", Currency::Eur, "12.00", Currency::Eur),
+        ("€12.50", Currency::Usd, "12.50", Currency::Eur),
+        ("£ 9.99", Currency::Eur, "9.99", Currency::Gbp),
+        ("12,50 EUR", Currency::Usd, "12.50", Currency::Eur),
+        ("12,50", Currency::Gbp, "12.50", Currency::Gbp),
+    ];
+
+    for (input, default_currency, amount, currency) in cases {
+        let price = parse_price(input, default_currency).unwrap_or_else(|e| panic!("{}: {}", input, e));
+        assert_eq!(price.amount.to_string(), amount, "{}", input);
+        assert_eq!(price.currency, currency, "{}", input);
+    }
+}
+
+#[test]
+fn bad_prices_are_errors() {
+    let cases = [
+        ("", PriceError::Empty),
+        (" \u{a0} ", PriceError::Empty),
+        ("Agotado", PriceError::NoAmount(String::from("Agotado"))),
+        ("€ ,", PriceError::NoAmount(String::from("€ ,"))),
+        // Too many digits for a Decimal
+        ("123456789012345678901234567890 €", PriceError::BadAmount(String::from("123456789012345678901234567890 €"))),
+    ];
+
+    for (input, error) in cases {
+        assert_eq!(parse_price(input, Currency::Eur), Err(error), "{:?}", input);
+    }
+}
+
+#[test]
+fn amounts_are_normalized() {
+    let cases = [
+        ("1.234,56", Some("1234.56")),
+        ("1,234.56", Some("1234.56")),
+        ("12,5", Some("12.5")),
+        ("12.50", Some("12.50")),
+        // One separator and 3 digits is a thousands separator
+        ("1.234", Some("1234")),
+        ("1,234", Some("1234")),
+        ("1.234.567", Some("1234567")),
+        ("1234", Some("1234")),
+        (",50", None),
+        ("", None),
+    ];
+
+    for (input, amount) in cases {
+        assert_eq!(normalize_amount(input).as_deref(), amount, "{:?}", input);
+    }
+}
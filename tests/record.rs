@@ -0,0 +1,106 @@
+// Runs recorded into a scratch directory and served back
+mod common;
+
+use std::sync::Mutex;
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use aragog::http::{Fetch, FetchedPage, Recorder, Replayer};
+use aragog::parser::ScrapeError;
+use common::Scratch;
+
+// Answers "<url> #<n>" to the n-th request, and fails on anything "down"
+#[derive(Default)]
+struct Network {
+    requests: Mutex<usize>,
+}
+
+impl Network {
+    fn answer(&self, url: &str) -> Result<FetchedPage, ScrapeError> {
+        let mut requests = self.requests.lock().unwrap();
+        *requests += 1;
+        match url.contains("down") {
+            true => Err(ScrapeError::Http(String::from("connection refused"))),
+            false => Ok(FetchedPage { status: 200, body: format!("{} #{}", url, requests) }),
+        }
+    }
+}
+
+#[async_trait]
+impl Fetch for Network {
+    async fn fetch(&self, url: &str) -> Result<FetchedPage, ScrapeError> {
+        self.answer(url)
+    }
+
+    async fn post_json(&self, url: &str, _: &Value, _: &[(String, String)]) -> Result<FetchedPage, ScrapeError> {
+        self.answer(url)
+    }
+}
+
+fn page(body: &str) -> Result<FetchedPage, ScrapeError> {
+    Ok(FetchedPage { status: 200, body: body.to_string() })
+}
+
+fn posted(name: &str, run_id: &str) -> Value {
+    json!({
+        "context": { "traceparent": run_id },
+        "body": { "name": name, "batch": run_id, "run_id": run_id, "scraped_at": run_id },
+    })
+}
+
+#[tokio::test]
+async fn replays_answer_as_recorded() {
+    let dir = Scratch::new("record-round-trip");
+    let recorder = Recorder::new(Network::default(), &dir).unwrap();
+    assert_eq!(recorder.fetch("https://shop/list").await, page("https://shop/list #1"));
+    assert_eq!(recorder.fetch("https://shop/down").await, Err(ScrapeError::Http(String::from("connection refused"))));
+    assert_eq!(recorder.post_json("https://backend/offers", &posted("Catan", "1"), &[]).await, page("https://backend/offers #3"));
+
+    let replayer = Replayer::new(&dir).unwrap();
+    assert_eq!(replayer.fetch("https://shop/list").await, page("https://shop/list #1"));
+    assert_eq!(replayer.fetch("https://shop/down").await, Err(ScrapeError::Http(String::from("connection refused"))));
+    assert_eq!(replayer.post_json("https://backend/offers", &posted("Catan", "1"), &[]).await, page("https://backend/offers #3"));
+    assert!(replayer.fetch("https://shop/other").await.is_err());
+}
+
+#[tokio::test]
+async fn repeated_requests_replay_in_order() {
+    let dir = Scratch::new("record-retries");
+    let recorder = Recorder::new(Network::default(), &dir).unwrap();
+    for _ in 0..2 {
+        recorder.fetch("https://shop/list").await.unwrap();
+    }
+
+    let replayer = Replayer::new(&dir).unwrap();
+    assert_eq!(replayer.fetch("https://shop/list").await, page("https://shop/list #1"));
+    assert_eq!(replayer.fetch("https://shop/list").await, page("https://shop/list #2"));
+    // Past the recording the last answer is repeated
+    assert_eq!(replayer.fetch("https://shop/list").await, page("https://shop/list #2"));
+}
+
+#[tokio::test]
+async fn posts_of_another_run_replay_the_same() {
+    let dir = Scratch::new("record-volatile");
+    let recorder = Recorder::new(Network::default(), &dir).unwrap();
+    recorder.post_json("https://backend/offers", &posted("Catan", "first run"), &[]).await.unwrap();
+
+    let replayer = Replayer::new(&dir).unwrap();
+    assert_eq!(replayer.post_json("https://backend/offers", &posted("Catan", "second run"), &[]).await, page("https://backend/offers #1"));
+    // Anything else in the body makes it another request
+    assert!(replayer.post_json("https://backend/offers", &posted("Azul", "second run"), &[]).await.is_err());
+}
+
+#[tokio::test]
+async fn webhook_tokens_stay_out_of_recordings() {
+    let dir = Scratch::new("record-webhook");
+    let hook = "https://discord.com/api/webhooks/1234/s3cr3t-t0ken";
+    let recorder = Recorder::new(Network::default(), &dir).unwrap().never_save(vec![hook.to_string()]);
+    assert_eq!(recorder.post_json(hook, &json!({ "content": "Catan" }), &[]).await, page(&format!("{} #1", hook)));
+    recorder.post_json("https://backend/offers", &posted("Catan", "1"), &[]).await.unwrap();
+
+    let files: Vec<_> = std::fs::read_dir(&*dir).unwrap().map(|entry| entry.unwrap().path()).collect();
+    assert_eq!(files.len(), 1);
+    for file in files {
+        assert!(!file.to_string_lossy().contains("s3cr3t"));
+        assert!(!std::fs::read_to_string(&file).unwrap().contains("s3cr3t"));
+    }
+}
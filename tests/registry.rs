@@ -0,0 +1,55 @@
+// Shop selections, against the shops of configuration.yaml
+use aragog::configuration::{get_configuration, ShopSettings};
+use aragog::parser::ShopRegistry;
+
+fn select(selection: &str) -> Result<Vec<String>, String> {
+    let configuration = get_configuration().expect("Failed to read configuration file");
+    let registry = ShopRegistry::new(&configuration.shops).unwrap();
+    registry.select(selection)
+        .map(|shops| shops.iter().map(|shop| shop.settings.id.clone()).collect())
+        .map_err(|e| e.to_string())
+}
+
+#[test]
+fn all_is_every_shop_in_order() {
+    assert_eq!(select("all").unwrap(), vec!["dracotienda", "jugamosotra", "dungeonmarvels"]);
+    // Order comes from the configuration, not from the selection
+    assert_eq!(select("dungeonmarvels,dracotienda").unwrap(), vec!["dracotienda", "dungeonmarvels"]);
+}
+
+#[test]
+fn exclusions_take_shops_away() {
+    assert_eq!(select("all,-jugamosotra").unwrap(), vec!["dracotienda", "dungeonmarvels"]);
+    assert_eq!(select("-jugamosotra,all").unwrap(), vec!["dracotienda", "dungeonmarvels"]);
+    // Only exclusions start from all
+    assert_eq!(select("-dracotienda,-jugamosotra").unwrap(), vec!["dungeonmarvels"]);
+    assert_eq!(select("dracotienda,-dracotienda").unwrap(), Vec::<String>::new());
+}
+
+#[test]
+fn unknown_shops_are_errors() {
+    assert!(select("dracotienda,nope").unwrap_err().contains("Unknown shop `nope`"));
+    assert!(select("all,-nope").unwrap_err().contains("Unknown shop `nope`"));
+}
+
+#[test]
+fn blanks_are_ignored() {
+    assert_eq!(select(" dracotienda , ,jugamosotra,").unwrap(), vec!["dracotienda", "jugamosotra"]);
+    assert_eq!(select("").unwrap(), Vec::<String>::new());
+    assert_eq!(select(" , ").unwrap(), Vec::<String>::new());
+}
+
+#[test]
+fn shops_that_can_not_scrape_are_errors() {
+    let configuration = get_configuration().expect("Failed to read configuration file");
+    let invalid = |change: fn(&mut ShopSettings)| {
+        let mut shops = configuration.shops.clone();
+        change(&mut shops[0]);
+        ShopRegistry::new(&shops).err().map(|e| e.to_string())
+    };
+
+    assert_eq!(invalid(|_| ()), None);
+    assert_eq!(invalid(|shop| shop.page_size = 0), Some(String::from("Shop `dracotienda` needs a page_size of at least 1, not 0")));
+    assert_eq!(invalid(|shop| shop.page_size = -24), Some(String::from("Shop `dracotienda` needs a page_size of at least 1, not -24")));
+    assert_eq!(invalid(|shop| shop.concurrency = 0), Some(String::from("Shop `dracotienda` needs a concurrency of at least 1")));
+}
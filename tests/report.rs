@@ -0,0 +1,81 @@
+// Run summaries and the thresholds that make a run fail
+use std::collections::BTreeMap;
+use color_eyre::Report;
+use aragog::configuration::ReportSettings;
+use aragog::parser::{ScrapeError, ScrapeStats};
+use aragog::report::{ReportFormat, RunSummary, ShopReport};
+use aragog::sink::Delivery;
+
+fn stats(entries_seen: usize, failures: &[(&str, usize)]) -> ScrapeStats {
+    ScrapeStats {
+        pages_fetched: 1,
+        entries_seen,
+        offers: entries_seen,
+        rejected: 0,
+        failures: failures.iter().map(|(kind, count)| (kind.to_string(), *count)).collect::<BTreeMap<_, _>>(),
+    }
+}
+
+fn thresholds(max_timeouts: Option<usize>) -> ReportSettings {
+    ReportSettings { max_failure_rate: 0.5, min_offers_posted: 1, max_timeouts }
+}
+
+#[test]
+fn failure_rate_is_per_entry_seen() {
+    let report = |entries_seen, timeouts, other_failures| ShopReport { entries_seen, timeouts, other_failures, ..Default::default() };
+
+    assert_eq!(report(0, 0, 0).failure_rate(), 0.0);
+    assert_eq!(report(10, 1, 1).failure_rate(), 0.2);
+    assert_eq!(report(4, 0, 4).failure_rate(), 1.0);
+    // Not a single entry to show is a full failure
+    assert_eq!(report(0, 0, 1).failure_rate(), 1.0);
+}
+
+#[test]
+fn deliveries_are_counted_by_kind() {
+    let mut summary = RunSummary::default();
+    summary.record_scrape("Dracotienda", &stats(4, &[("bad_price", 1)]));
+    summary.record_delivery("Dracotienda", &Ok(Delivery::Accepted));
+    summary.record_delivery("Dracotienda", &Ok(Delivery::Unmatched));
+    summary.record_delivery("Dracotienda", &Err(Report::from(ScrapeError::BackendRejected(408))));
+    summary.record_delivery("Dracotienda", &Err(color_eyre::eyre::eyre!("Bulk publisher stopped")));
+
+    let report = &summary.shops["Dracotienda"];
+    assert_eq!((report.offers_posted, report.unmatched, report.timeouts, report.other_failures), (1, 1, 1, 2));
+    assert_eq!(report.failures.keys().collect::<Vec<_>>(), vec!["backend_rejected(408)", "bad_price", "publish"]);
+}
+
+#[test]
+fn good_runs_have_no_violations() {
+    let mut summary = RunSummary::default();
+    summary.record_scrape("Dracotienda", &stats(10, &[("bad_price", 5)]));
+    summary.record_delivery("Dracotienda", &Ok(Delivery::Accepted));
+
+    assert_eq!(summary.violations(&thresholds(None)), Vec::<String>::new());
+}
+
+#[test]
+fn every_threshold_is_reported() {
+    let mut summary = RunSummary::default();
+    summary.record_scrape("Dracotienda", &stats(2, &[("layout_changed", 2)]));
+    summary.record_scrape("JugamosOtra", &stats(10, &[]));
+    for _ in 0..3 {
+        summary.record_delivery("JugamosOtra", &Err(Report::from(ScrapeError::BackendRejected(408))));
+    }
+    summary.record_delivery("JugamosOtra", &Ok(Delivery::Accepted));
+
+    assert_eq!(summary.violations(&thresholds(Some(2))), vec![
+        "Dracotienda: 100% of the entries failed",
+        "Dracotienda: only 0 offers posted",
+        "JugamosOtra: 3 backend timeouts",
+    ]);
+    // Timeouts are only limited when asked
+    assert_eq!(summary.violations(&thresholds(None)).len(), 2);
+}
+
+#[test]
+fn report_formats_are_known() {
+    assert_eq!("table".parse(), Ok(ReportFormat::Table));
+    assert_eq!("json".parse(), Ok(ReportFormat::Json));
+    assert!("xml".parse::<ReportFormat>().is_err());
+}
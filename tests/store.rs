@@ -0,0 +1,97 @@
+// Price history in a scratch SQLite database
+mod common;
+
+use rusqlite::Connection;
+use aragog::store::{canonical_url, Store};
+use aragog::types::Offer;
+use common::{offer, Scratch};
+
+fn at(url: &str, offer_price: i64) -> Offer {
+    let mut offer = offer("Dracotienda", "Catan", 40, offer_price).unwrap();
+    offer.url = url.to_string();
+    offer
+}
+
+fn count(connection: &Connection, sql: &str) -> i64 {
+    connection.query_row(sql, [], |row| row.get(0)).unwrap()
+}
+
+#[test]
+fn urls_are_canonical() {
+    let cases = [
+        ("https://Shop.COM/catan/", "https://shop.com/catan"),
+        ("https://shop.com/catan#reviews", "https://shop.com/catan"),
+        ("https://shop.com/catan?utm_source=x&UTM_MEDIUM=y&fbclid=z", "https://shop.com/catan"),
+        ("https://shop.com/index.php?id_product=12&controller=product", "https://shop.com/index.php?id_product=12&controller=product"),
+        ("https://shop.com/index.php?utm_source=x&id_product=12&gclid=y#top", "https://shop.com/index.php?id_product=12"),
+        ("https://shop.com/Catan?", "https://shop.com/Catan"),
+    ];
+
+    for (url, canonical) in cases {
+        assert_eq!(canonical_url(url), canonical, "{}", url);
+    }
+}
+
+#[test]
+fn every_offer_is_an_observation_of_its_product() {
+    let dir = Scratch::new("store-record");
+    let store = Store::open(&dir.join("aragog.db")).unwrap();
+
+    store.record(&at("https://shop.com/catan", 30)).unwrap();
+    store.record(&at("https://SHOP.com/catan/?utm_source=newsletter", 28)).unwrap();
+    store.record(&at("https://shop.com/index.php?id_product=12", 30)).unwrap();
+    store.record(&at("https://shop.com/index.php?id_product=13", 30)).unwrap();
+
+    let connection = Connection::open(dir.join("aragog.db")).unwrap();
+    assert_eq!(count(&connection, "SELECT COUNT(*) FROM products"), 3);
+    assert_eq!(count(&connection, "SELECT COUNT(*) FROM observations"), 4);
+    let prices: Vec<(String, String, String, String)> = connection
+        .prepare("SELECT o.offer_price, o.status, o.condition, o.product_type FROM observations o JOIN products p ON p.id = o.product_id
+                  WHERE p.url = 'https://shop.com/catan' ORDER BY o.id").unwrap()
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))).unwrap()
+        .collect::<Result<_, _>>().unwrap();
+    assert_eq!(prices, vec![
+        (String::from("30"), String::from("in_stock"), String::from("new"), String::from("base_game")),
+        (String::from("28"), String::from("in_stock"), String::from("new"), String::from("base_game")),
+    ]);
+}
+
+#[test]
+fn older_databases_get_the_new_columns() {
+    let dir = Scratch::new("store-migration");
+    let path = dir.join("aragog.db");
+    // As the first version of the store left it
+    let old = Connection::open(&path).unwrap();
+    old.execute_batch("
+        CREATE TABLE products (
+            id INTEGER PRIMARY KEY, shop TEXT NOT NULL, url TEXT NOT NULL, name TEXT NOT NULL,
+            first_seen TEXT NOT NULL, last_seen TEXT NOT NULL, UNIQUE (shop, url)
+        );
+        CREATE TABLE observations (
+            id INTEGER PRIMARY KEY, product_id INTEGER NOT NULL REFERENCES products (id),
+            run_id TEXT NOT NULL, batch TEXT NOT NULL, observed_at TEXT NOT NULL,
+            normal_price TEXT NOT NULL, offer_price TEXT NOT NULL, currency TEXT NOT NULL,
+            availability TEXT NOT NULL
+        );
+        INSERT INTO products VALUES (1, 'Dracotienda', 'https://example.com/Catan', 'Catan', 'then', 'then');
+        INSERT INTO observations VALUES (1, 1, 'old', 'old', 'then', '40', '35', 'EUR', 'En stock');
+    ").unwrap();
+    drop(old);
+
+    let store = Store::open(&path).unwrap();
+    store.record(&at("https://example.com/Catan", 30)).unwrap();
+    // Opening again finds nothing left to add
+    Store::open(&path).unwrap();
+
+    let connection = Connection::open(&path).unwrap();
+    let rows: Vec<(String, String, Option<String>, String, String)> = connection
+        .prepare("SELECT run_id, status, language, condition, product_type FROM observations ORDER BY id").unwrap()
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?))).unwrap()
+        .collect::<Result<_, _>>().unwrap();
+    assert_eq!(rows, vec![
+        (String::from("old"), String::from("unknown"), None, String::from("new"), String::from("base_game")),
+        (String::from("test"), String::from("in_stock"), None, String::from("new"), String::from("base_game")),
+    ]);
+    assert_eq!(count(&connection, "SELECT COUNT(*) FROM products"), 1);
+}
+
@@ -0,0 +1,91 @@
+// Offers the backend could not match, kept for review in a scratch database
+mod common;
+
+use async_trait::async_trait;
+use color_eyre::Report;
+use rusqlite::Connection;
+use aragog::sink::{Delivery, OfferSink, UnmatchedSink};
+use aragog::store::{Store, UnmatchedTitle};
+use aragog::telemetry::SpannedMessage;
+use aragog::types::Offer;
+use common::{offer, Scratch};
+
+// Matches nothing but "Catan"
+struct Backend;
+
+#[async_trait]
+impl OfferSink for Backend {
+    async fn publish(&self, offer: &SpannedMessage<Offer>) -> Result<Delivery, Report> {
+        match offer.body().name.as_str() {
+            "Catan" => Ok(Delivery::Accepted),
+            _ => Ok(Delivery::Unmatched),
+        }
+    }
+}
+
+fn at(shop_name: &str, name: &str, url: &str) -> Offer {
+    let mut offer = offer(shop_name, name, 40, 30).unwrap();
+    offer.url = url.to_string();
+    offer
+}
+
+fn title(name: &str, times_seen: usize, shops: usize) -> UnmatchedTitle {
+    UnmatchedTitle { name: name.to_string(), times_seen, shops, raw_name: name.to_string() }
+}
+
+#[test]
+fn the_same_listing_is_counted_not_repeated() {
+    let dir = Scratch::new("unmatched-dedup");
+    let store = Store::open(&dir.join("aragog.db")).unwrap();
+
+    store.record_unmatched(&at("Dracotienda", "Azul", "https://shop.com/azul")).unwrap();
+    store.record_unmatched(&at("Dracotienda", "Azul", "https://shop.com/azul/?utm_source=x")).unwrap();
+    store.record_unmatched(&at("Dracotienda", "Azul", "https://shop.com/azul-2")).unwrap();
+
+    let connection = Connection::open(dir.join("aragog.db")).unwrap();
+    let rows: Vec<(String, i64)> = connection
+        .prepare("SELECT url, times_seen FROM unmatched ORDER BY id").unwrap()
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?))).unwrap()
+        .collect::<Result<_, _>>().unwrap();
+    assert_eq!(rows, vec![
+        (String::from("https://shop.com/azul"), 2),
+        (String::from("https://shop.com/azul-2"), 1),
+    ]);
+}
+
+#[test]
+fn top_titles_are_grouped_by_name() {
+    let dir = Scratch::new("unmatched-top");
+    let store = Store::open(&dir.join("aragog.db")).unwrap();
+
+    for _ in 0..2 {
+        store.record_unmatched(&at("Dracotienda", "Wingspan", "https://draco.com/wingspan")).unwrap();
+    }
+    store.record_unmatched(&at("Jugamosotra", "Wingspan", "https://jugamos.com/wingspan")).unwrap();
+    store.record_unmatched(&at("Dracotienda", "Azul", "https://draco.com/azul")).unwrap();
+    store.record_unmatched(&at("Jugamosotra", "Azul", "https://jugamos.com/azul")).unwrap();
+    store.record_unmatched(&at("Dracotienda", "Terraforming Mars", "https://draco.com/tm")).unwrap();
+    store.record_unmatched(&at("Jugamosotra", "Brass", "https://jugamos.com/brass")).unwrap();
+
+    assert_eq!(store.top_unmatched(10).unwrap(), vec![
+        title("Wingspan", 3, 2),
+        title("Azul", 2, 2),
+        // Ties go by name
+        title("Brass", 1, 1),
+        title("Terraforming Mars", 1, 1),
+    ]);
+    assert_eq!(store.top_unmatched(1).unwrap(), vec![title("Wingspan", 3, 2)]);
+}
+
+#[tokio::test]
+async fn only_unmatched_offers_are_kept() {
+    let dir = Scratch::new("unmatched-sink");
+    let store = Store::open(&dir.join("aragog.db")).unwrap();
+    let sink = UnmatchedSink::new(Box::new(Backend), store.clone());
+
+    assert_eq!(sink.publish(&offer("Dracotienda", "Catan", 40, 30)).await.unwrap(), Delivery::Accepted);
+    assert_eq!(sink.publish(&offer("Dracotienda", "Azul", 40, 30)).await.unwrap(), Delivery::Unmatched);
+    assert_eq!(sink.publish(&offer("Dracotienda", "Azul", 40, 30)).await.unwrap(), Delivery::Unmatched);
+
+    assert_eq!(store.top_unmatched(10).unwrap(), vec![title("Azul", 2, 1)]);
+}
@@ -0,0 +1,119 @@
+// Webhooks against a throwaway HTTP listener on localhost
+mod common;
+
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::sync::Semaphore;
+use aragog::configuration::WebhookSettings;
+use aragog::http::Fetch;
+use aragog::sink::{OfferSink, WebhookSink};
+use common::offer;
+
+// Answers every request with the next status of `statuses` (200 once they run
+// out) and keeps the bodies it got
+async fn listen(statuses: Vec<u16>) -> (String, Arc<Mutex<Vec<serde_json::Value>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    let bodies = Arc::new(Mutex::new(vec![]));
+
+    let received = bodies.clone();
+    tokio::spawn(async move {
+        let mut statuses = statuses.into_iter();
+        while let Ok((mut socket, _)) = listener.accept().await {
+            let mut request = vec![];
+            let mut buffer = [0; 4096];
+            // Headers first, then as much body as Content-Length says
+            let body = loop {
+                let read = socket.read(&mut buffer).await.unwrap();
+                request.extend_from_slice(&buffer[..read]);
+                let text = String::from_utf8_lossy(&request).to_string();
+                if let Some(end) = text.find("\r\n\r\n") {
+                    let length: usize = text.lines()
+                        .find_map(|line| line.to_lowercase().strip_prefix("content-length:").map(|value| value.trim().parse().unwrap()))
+                        .unwrap_or(0);
+                    if request.len() >= end + 4 + length {
+                        break request[end + 4..end + 4 + length].to_vec();
+                    }
+                }
+            };
+            received.lock().unwrap().push(serde_json::from_slice(&body).unwrap());
+
+            let status = statuses.next().unwrap_or(200);
+            let response = format!("HTTP/1.1 {} X\r\ncontent-length: 0\r\nconnection: close\r\n\r\n", status);
+            socket.write_all(response.as_bytes()).await.unwrap();
+        }
+    });
+
+    (url, bodies)
+}
+
+fn settings(url: &str) -> WebhookSettings {
+    WebhookSettings {
+        id: String::from("test"),
+        url: Some(url.to_string()),
+        url_env: None,
+        template: String::from(r#"{"content": "{{name}} por {{offer_price}} {{currency}} (-{{discount}}%) en {{shop}}"}"#),
+        shops: vec![],
+        product_types: vec![],
+        min_discount: 20,
+        retries: 2,
+        retry_delay_secs: 0,
+    }
+}
+
+#[tokio::test]
+async fn sends_offers_that_pass_the_filters() {
+    let (url, bodies) = listen(vec![]).await;
+    let client: Arc<dyn Fetch> = Arc::new(reqwest::Client::new());
+    let sink = WebhookSink::new(settings(&url), vec![String::from("Dracotienda")], client, Arc::new(Semaphore::new(1))).unwrap();
+
+    sink.publish(&offer("Dracotienda", "Catan \"Edición\"", 40, 30)).await.unwrap();
+    // Not discounted enough
+    sink.publish(&offer("Dracotienda", "Azul", 40, 35)).await.unwrap();
+    // Another shop
+    sink.publish(&offer("JugamosOtra", "Wingspan", 60, 30)).await.unwrap();
+
+    assert_eq!(*bodies.lock().unwrap(), vec![
+        serde_json::json!({ "content": "Catan \"Edición\" por 30.00 EUR (-25%) en Dracotienda" }),
+    ]);
+}
+
+#[tokio::test]
+async fn retries_server_errors() {
+    let (url, bodies) = listen(vec![503, 429]).await;
+    let client: Arc<dyn Fetch> = Arc::new(reqwest::Client::new());
+    let sink = WebhookSink::new(settings(&url), vec![], client, Arc::new(Semaphore::new(1))).unwrap();
+
+    sink.publish(&offer("Dracotienda", "Catan", 40, 30)).await.unwrap();
+
+    assert_eq!(bodies.lock().unwrap().len(), 3);
+}
+
+#[test]
+fn bad_templates_are_refused_upfront() {
+    let client: Arc<dyn Fetch> = Arc::new(reqwest::Client::new());
+    let mut broken = settings("http://127.0.0.1:1/hook");
+    broken.template = String::from(r#"{"content": "{{title}}"}"#);
+    assert!(WebhookSink::new(broken, vec![], client.clone(), Arc::new(Semaphore::new(1))).is_err());
+
+    let mut broken = settings("http://127.0.0.1:1/hook");
+    broken.template = String::from(r#"{"content": {{name}}}"#);
+    assert!(WebhookSink::new(broken, vec![], client, Arc::new(Semaphore::new(1))).is_err());
+}
+
+#[tokio::test]
+async fn values_are_never_filled_again() {
+    let (url, bodies) = listen(vec![]).await;
+    let client: Arc<dyn Fetch> = Arc::new(reqwest::Client::new());
+    let mut settings = settings(&url);
+    settings.template = String::from(r#"{"content": "{{name}} {{url}}"}"#);
+    settings.min_discount = 0;
+    let sink = WebhookSink::new(settings, vec![], client, Arc::new(Semaphore::new(1))).unwrap();
+
+    sink.publish(&offer("Dracotienda", "{{title}}", 40, 30)).await.unwrap();
+
+    assert_eq!(*bodies.lock().unwrap(), vec![
+        serde_json::json!({ "content": "{{title}} https://example.com/{{title}}" }),
+    ]);
+}